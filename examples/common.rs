@@ -3,7 +3,7 @@ use bevy::prelude::*;
 use bevy_advanced_input::{
     config::InputConfig,
     input_id::InputId,
-    plugin::InputBindingPlugin,
+    plugin::{InputBindingPlugin, InputSystem},
     user_input::{InputAxisType, MouseAxisType, UserInputHandle, UserInputSet},
 };
 
@@ -59,9 +59,9 @@ fn main() {
         .add_system(
             process_player_input
                 .system()
-                .after("raw_input")
+                .after(InputSystem::RawInput)
                 .label("input"),
-        ) //* Step 7: create process input system, it must be runned after out "raw_input" system
+        ) //* Step 7: create process input system, it must be runned after out InputSystem::RawInput system
         .run();
 }
 
@@ -1,4 +1,8 @@
-use bevy::{prelude::KeyCode, utils::HashMap};
+use bevy::{
+    math::Vec2,
+    prelude::KeyCode,
+    utils::{HashMap, HashSet},
+};
 use std::hash::Hash;
 
 use crate::common::InsertOrGet;
@@ -13,6 +17,62 @@ where
     pub(crate) convert_pressed_key_to: HashMap<InputAxisType, InputAxisType>,
     axis_multiplyer: HashMap<BindingType, HashMap<InputAxisType, f32>>,
     common_axis_multiplyer: HashMap<InputAxisType, f32>,
+    #[serde(default)]
+    mouse_settings: MouseSettings,
+    #[serde(default)]
+    toggle_bindings: HashSet<BindingType>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct MouseSettings {
+    pub sensitivity: Vec2,
+    pub raw_input: bool,
+    pub acceleration: f32,
+    pub smoothing_frames: usize,
+    pub dpi: f32,
+    pub reference_dpi: f32,
+    pub pixels_per_line: f32,
+}
+
+impl MouseSettings {
+    pub(crate) fn apply(&self, delta: Vec2) -> Vec2 {
+        let mut delta = delta * self.dpi_scale() * self.sensitivity;
+        if !self.raw_input {
+            delta *= 1.0 + self.acceleration * delta.length();
+        }
+        delta
+    }
+
+    pub(crate) fn pixels_to_lines(&self, delta: Vec2) -> Vec2 {
+        if self.pixels_per_line > 0.0 {
+            delta / self.pixels_per_line
+        } else {
+            delta / Self::default().pixels_per_line
+        }
+    }
+
+    fn dpi_scale(&self) -> f32 {
+        if self.dpi > 0.0 && self.reference_dpi > 0.0 {
+            self.reference_dpi / self.dpi
+        } else {
+            1.0
+        }
+    }
+}
+
+impl Default for MouseSettings {
+    fn default() -> Self {
+        Self {
+            sensitivity: Vec2::ONE,
+            raw_input: true,
+            acceleration: 0.0,
+            smoothing_frames: 0,
+            dpi: 800.0,
+            reference_dpi: 800.0,
+            pixels_per_line: 20.0,
+        }
+    }
 }
 
 impl<BindingType> InputConfig<BindingType>
@@ -24,6 +84,8 @@ where
             convert_pressed_key_to: HashMap::default(),
             axis_multiplyer: HashMap::default(),
             common_axis_multiplyer: HashMap::default(),
+            mouse_settings: MouseSettings::default(),
+            toggle_bindings: HashSet::default(),
         }
     }
 }
@@ -49,6 +111,26 @@ where
         self.common_axis_multiplyer.insert(input_axis, modifier);
     }
 
+    pub fn set_toggle(&mut self, binding: BindingType, toggle: bool) {
+        if toggle {
+            self.toggle_bindings.insert(binding);
+        } else {
+            self.toggle_bindings.remove(&binding);
+        }
+    }
+
+    pub fn is_toggle(&self, binding: &BindingType) -> bool {
+        self.toggle_bindings.contains(binding)
+    }
+
+    pub fn set_mouse_settings(&mut self, settings: MouseSettings) {
+        self.mouse_settings = settings;
+    }
+
+    pub fn get_mouse_settings(&self) -> &MouseSettings {
+        &self.mouse_settings
+    }
+
     pub fn get_default_value(&self, input_axis: &InputAxisType) -> f32 {
         if let Some(value) = self.common_axis_multiplyer.get(&input_axis) {
             return *value;
@@ -56,3 +138,59 @@ where
         1.0
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bevy::math::Vec2;
+
+    use super::MouseSettings;
+
+    #[test]
+    fn dpi_normalizes_motion() {
+        let settings = MouseSettings {
+            dpi: 1600.0,
+            reference_dpi: 800.0,
+            ..Default::default()
+        };
+        assert_eq!(settings.apply(Vec2::new(4.0, -2.0)), Vec2::new(2.0, -1.0));
+    }
+
+    #[test]
+    fn invalid_dpi_keeps_motion_finite() {
+        for dpi in [0.0, -800.0, f32::NAN] {
+            let settings = MouseSettings {
+                dpi,
+                ..Default::default()
+            };
+            assert_eq!(settings.apply(Vec2::new(4.0, -2.0)), Vec2::new(4.0, -2.0));
+        }
+    }
+
+    #[test]
+    fn pixel_scroll_is_converted_to_lines() {
+        let mut settings = MouseSettings {
+            pixels_per_line: 10.0,
+            ..Default::default()
+        };
+        assert_eq!(
+            settings.pixels_to_lines(Vec2::new(0.0, 25.0)),
+            Vec2::new(0.0, 2.5)
+        );
+        settings.pixels_per_line = 0.0;
+        assert_eq!(
+            settings.pixels_to_lines(Vec2::new(0.0, 40.0)),
+            Vec2::new(0.0, 2.0)
+        );
+    }
+
+    #[test]
+    fn acceleration_is_skipped_for_raw_input() {
+        let mut settings = MouseSettings {
+            acceleration: 0.5,
+            ..Default::default()
+        };
+        assert_eq!(settings.apply(Vec2::new(2.0, 0.0)), Vec2::new(2.0, 0.0));
+        settings.raw_input = false;
+        assert_eq!(settings.apply(Vec2::new(2.0, 0.0)), Vec2::new(4.0, 0.0));
+    }
+}
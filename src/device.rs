@@ -0,0 +1,349 @@
+use std::any::TypeId;
+
+use bevy::{
+    prelude::Gamepad,
+    utils::{HashMap, HashSet},
+};
+
+use crate::user_input::{InputAxisType, InputSource};
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum InputDevice {
+    Keyboard,
+    Mouse,
+    Gamepad(Gamepad),
+}
+
+impl InputDevice {
+    // Gamepad inputs don't store the gamepad, so any gamepad produces them
+    pub(crate) fn produces(&self, input: &InputAxisType) -> bool {
+        let source = input.input_source();
+        match self {
+            InputDevice::Keyboard => source == InputSource::Keyboard,
+            InputDevice::Mouse => source == InputSource::Mouse,
+            InputDevice::Gamepad(_) => source == InputSource::Gamepad,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct InputDomain(TypeId);
+
+impl InputDomain {
+    pub fn of<InputType: 'static, KeyType: 'static>() -> Self {
+        Self(TypeId::of::<(InputType, KeyType)>())
+    }
+}
+
+// Unclaimed devices reach every domain, claim devices meant for one domain
+#[derive(Default)]
+pub struct DeviceRouter {
+    owners: HashMap<InputDevice, InputDomain>,
+}
+
+impl DeviceRouter {
+    pub fn claim(&mut self, device: InputDevice, domain: InputDomain) -> bool {
+        match self.owners.get(&device) {
+            Some(owner) if *owner != domain => false,
+            _ => {
+                self.owners.insert(device, domain);
+                true
+            }
+        }
+    }
+
+    pub fn release(&mut self, device: InputDevice) {
+        self.owners.remove(&device);
+    }
+
+    pub fn get_owner(&self, device: InputDevice) -> Option<InputDomain> {
+        self.owners.get(&device).copied()
+    }
+
+    pub(crate) fn accepts(&self, device: InputDevice, domain: InputDomain) -> bool {
+        self.owners
+            .get(&device)
+            .map_or(true, |owner| *owner == domain)
+    }
+
+    pub(crate) fn get_blocked(&self, domain: InputDomain) -> HashSet<InputDevice> {
+        self.owners
+            .iter()
+            .filter(|(_, owner)| **owner != domain)
+            .map(|(device, _)| *device)
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct DeviceClaim {
+    pub(crate) input_id: u8,
+    pub(crate) inputs: Option<HashSet<InputAxisType>>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct DeviceClaims {
+    claims: HashMap<InputDevice, Vec<DeviceClaim>>,
+    remote: HashSet<u8>,
+}
+
+impl DeviceClaims {
+    pub(crate) fn claim(
+        &mut self,
+        input_id: u8,
+        device: InputDevice,
+        inputs: Option<HashSet<InputAxisType>>,
+    ) -> bool {
+        let conflicts = self.claims.get(&device).map_or(false, |claims| {
+            claims
+                .iter()
+                .filter(|claim| claim.input_id != input_id)
+                .any(|claim| claim.inputs.is_none() || inputs.is_none())
+        });
+        if conflicts {
+            return false;
+        }
+        let claims = self.claims.entry(device).or_insert_with(Vec::new);
+        claims.retain(|claim| claim.input_id != input_id);
+        claims.push(DeviceClaim { input_id, inputs });
+        true
+    }
+
+    pub(crate) fn release(&mut self, input_id: u8, device: InputDevice) {
+        if let Some(claims) = self.claims.get_mut(&device) {
+            claims.retain(|claim| claim.input_id != input_id);
+            if claims.is_empty() {
+                self.claims.remove(&device);
+            }
+        }
+    }
+
+    pub(crate) fn set_remote(&mut self, input_id: u8, remote: bool) {
+        if remote {
+            self.remote.insert(input_id);
+        } else {
+            self.remote.remove(&input_id);
+        }
+    }
+
+    pub(crate) fn is_remote(&self, input_id: u8) -> bool {
+        self.remote.contains(&input_id)
+    }
+
+    pub(crate) fn release_player(&mut self, input_id: u8) {
+        self.remote.remove(&input_id);
+        for claims in self.claims.values_mut() {
+            claims.retain(|claim| claim.input_id != input_id);
+        }
+        self.claims.retain(|_, claims| !claims.is_empty());
+    }
+
+    pub(crate) fn get_owners(&self, device: InputDevice) -> Vec<u8> {
+        self.claims.get(&device).map_or(Vec::new(), |claims| {
+            claims.iter().map(|claim| claim.input_id).collect()
+        })
+    }
+
+    pub(crate) fn get_player_devices(&self, input_id: u8) -> Vec<InputDevice> {
+        self.claims
+            .iter()
+            .filter(|(_, claims)| claims.iter().any(|claim| claim.input_id == input_id))
+            .map(|(device, _)| *device)
+            .collect()
+    }
+
+    fn get_claim(&self, input_id: u8, device: InputDevice) -> Option<&DeviceClaim> {
+        self.claims
+            .get(&device)
+            .and_then(|claims| claims.iter().find(|claim| claim.input_id == input_id))
+    }
+
+    // Shared keyboard and mouse don't switch the source of players with a claimed gamepad
+    pub(crate) fn is_source(
+        &self,
+        input_id: u8,
+        device: InputDevice,
+        inputs: &[InputAxisType],
+    ) -> bool {
+        if self.remote.contains(&input_id) {
+            return false;
+        }
+        match self.get_claim(input_id, device) {
+            Some(claim) => match &claim.inputs {
+                Some(claimed) => inputs.iter().any(|input| claimed.contains(input)),
+                None => true,
+            },
+            None => {
+                self.accepts_event(input_id, device, inputs)
+                    && !self.claims.iter().any(|(claimed_device, claims)| {
+                        matches!(claimed_device, InputDevice::Gamepad(_))
+                            && claims.iter().any(|claim| claim.input_id == input_id)
+                    })
+            }
+        }
+    }
+
+    pub(crate) fn accepts_event(
+        &self,
+        input_id: u8,
+        device: InputDevice,
+        inputs: &[InputAxisType],
+    ) -> bool {
+        if self.get_claim(input_id, device).is_some() {
+            inputs
+                .iter()
+                .any(|input| self.accepts(input_id, device, Some(input)))
+        } else {
+            self.accepts(input_id, device, None)
+                && inputs
+                    .iter()
+                    .all(|input| self.accepts(input_id, device, Some(input)))
+        }
+    }
+
+    // Unclaimed keyboard and mouse reach every InputId, unclaimed gamepad the InputId with its index
+    pub(crate) fn accepts(
+        &self,
+        input_id: u8,
+        device: InputDevice,
+        input: Option<&InputAxisType>,
+    ) -> bool {
+        if self.remote.contains(&input_id) {
+            return false;
+        }
+        if let Some(claim) = self.get_claim(input_id, device) {
+            return match (&claim.inputs, input) {
+                (Some(inputs), Some(input)) => inputs.contains(input),
+                _ => true,
+            };
+        }
+        let unclaimed = self.claims.get(&device).map_or(true, |claims| {
+            claims.iter().all(|claim| match (&claim.inputs, input) {
+                (None, _) => false,
+                (Some(inputs), Some(input)) => !inputs.contains(input),
+                (Some(_), None) => true,
+            })
+        });
+        unclaimed
+            && match device {
+                InputDevice::Gamepad(gamepad) => gamepad.0 as u8 == input_id,
+                InputDevice::Keyboard | InputDevice::Mouse => true,
+            }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{
+        app::Events,
+        input::{keyboard::KeyboardInput, ElementState},
+        prelude::{AppBuilder, KeyCode},
+    };
+
+    use super::{DeviceRouter, InputDevice, InputDomain};
+    use crate::{
+        input_id::InputId,
+        plugin::InputBindingPlugin,
+        testing::{
+            advance_frame,
+            tests::{handle_mut, test_app, TestBinding},
+        },
+        user_input::{InputAxisType, UserInputHandle, UserInputSet},
+    };
+
+    type EditorHandle = UserInputHandle<u8, u8>;
+
+    fn key_state(app: &mut AppBuilder, input_id: &InputId) -> Option<ElementState> {
+        handle_mut(app)
+            .to_handle(input_id)
+            .unwrap()
+            .get_key_state(TestBinding::Jump)
+    }
+
+    #[test]
+    fn device_taken_by_other_domain_releases_held_keys() {
+        let mut set = UserInputSet::new();
+        set.begin_key(TestBinding::Jump)
+            .add(&[InputAxisType::KeyboardButton(KeyCode::Space)]);
+        let (mut app, input_id) = test_app(set);
+
+        handle_mut(&mut app).press_key(KeyCode::Space);
+        advance_frame(&mut app);
+        assert_eq!(key_state(&mut app, &input_id), Some(ElementState::Pressed));
+
+        let other_domain = InputDomain::of::<u8, u8>();
+        assert!(app
+            .world_mut()
+            .get_resource_mut::<DeviceRouter>()
+            .unwrap()
+            .claim(InputDevice::Keyboard, other_domain));
+        advance_frame(&mut app);
+        assert_eq!(key_state(&mut app, &input_id), Some(ElementState::Released));
+        advance_frame(&mut app);
+        assert_eq!(key_state(&mut app, &input_id), None);
+    }
+
+    fn send_space(app: &mut AppBuilder, state: ElementState) {
+        app.world_mut()
+            .get_resource_mut::<Events<KeyboardInput>>()
+            .unwrap()
+            .send(KeyboardInput {
+                scan_code: 57,
+                key_code: Some(KeyCode::Space),
+                state,
+            });
+    }
+
+    fn editor_key_state(app: &mut AppBuilder, input_id: &InputId) -> Option<ElementState> {
+        app.world()
+            .get_resource::<EditorHandle>()
+            .unwrap()
+            .to_handle(input_id)
+            .unwrap()
+            .get_key_state(0)
+    }
+
+    #[test]
+    fn unclaimed_device_reaches_every_plugin_instance() {
+        let mut set = UserInputSet::new();
+        set.begin_key(TestBinding::Jump)
+            .add(&[InputAxisType::KeyboardButton(KeyCode::Space)]);
+        let (mut app, input_id) = test_app(set);
+        app.add_plugin(InputBindingPlugin::<u8, u8>::default());
+        {
+            let mut editor = app.world_mut().get_resource_mut::<EditorHandle>().unwrap();
+            let mut editor_set = UserInputSet::new();
+            editor_set
+                .begin_key(0)
+                .add(&[InputAxisType::KeyboardButton(KeyCode::Space)]);
+            editor.add_input(0, editor_set);
+            editor.switch_input(&input_id, 0);
+        }
+
+        send_space(&mut app, ElementState::Pressed);
+        advance_frame(&mut app);
+        assert_eq!(key_state(&mut app, &input_id), Some(ElementState::Pressed));
+        assert_eq!(
+            editor_key_state(&mut app, &input_id),
+            Some(ElementState::Pressed)
+        );
+
+        // Claim moves the keyboard to the editor, the game releases its held key
+        assert!(app
+            .world_mut()
+            .get_resource_mut::<DeviceRouter>()
+            .unwrap()
+            .claim(InputDevice::Keyboard, InputDomain::of::<u8, u8>()));
+        advance_frame(&mut app);
+        assert_eq!(key_state(&mut app, &input_id), Some(ElementState::Released));
+        assert_eq!(editor_key_state(&mut app, &input_id), None);
+
+        send_space(&mut app, ElementState::Released);
+        advance_frame(&mut app);
+        assert_eq!(key_state(&mut app, &input_id), None);
+        assert_eq!(
+            editor_key_state(&mut app, &input_id),
+            Some(ElementState::Released)
+        );
+    }
+}
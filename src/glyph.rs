@@ -0,0 +1,100 @@
+use bevy::{prelude::GamepadButtonType, utils::HashMap};
+use serde::{Deserialize, Serialize};
+
+use crate::{common::InsertOrGet, user_input::InputAxisType};
+
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug, Deserialize, Serialize)]
+pub enum GamepadLayout {
+    Xbox,
+    PlayStation,
+    Nintendo,
+}
+
+impl Default for GamepadLayout {
+    fn default() -> Self {
+        Self::Xbox
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct GlyphTable<Glyph> {
+    glyphs: HashMap<InputAxisType, Glyph>,
+    layout_glyphs: HashMap<GamepadLayout, HashMap<InputAxisType, Glyph>>,
+}
+
+impl<Glyph> GlyphTable<Glyph> {
+    pub fn new() -> Self {
+        Self {
+            glyphs: HashMap::default(),
+            layout_glyphs: HashMap::default(),
+        }
+    }
+
+    pub fn insert(&mut self, input: InputAxisType, glyph: Glyph) -> &mut Self {
+        self.glyphs.insert(input, glyph);
+        self
+    }
+
+    pub fn insert_for_layout(
+        &mut self,
+        layout: GamepadLayout,
+        input: InputAxisType,
+        glyph: Glyph,
+    ) -> &mut Self {
+        self.layout_glyphs
+            .insert_or_get(layout)
+            .insert(input, glyph);
+        self
+    }
+
+    pub fn get(&self, input: &InputAxisType, layout: GamepadLayout) -> Option<&Glyph> {
+        self.layout_glyphs
+            .get(&layout)
+            .and_then(|glyphs| glyphs.get(input))
+            .or_else(|| self.glyphs.get(input))
+    }
+}
+
+impl<Glyph> Default for GlyphTable<Glyph> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GlyphTable<String> {
+    pub fn with_gamepad_names() -> Self {
+        let mut table = Self::new();
+        let buttons = [
+            (GamepadButtonType::South, ["A", "Cross", "B"]),
+            (GamepadButtonType::East, ["B", "Circle", "A"]),
+            (GamepadButtonType::West, ["X", "Square", "Y"]),
+            (GamepadButtonType::North, ["Y", "Triangle", "X"]),
+            (GamepadButtonType::LeftTrigger, ["LB", "L1", "L"]),
+            (GamepadButtonType::LeftTrigger2, ["LT", "L2", "ZL"]),
+            (GamepadButtonType::RightTrigger, ["RB", "R1", "R"]),
+            (GamepadButtonType::RightTrigger2, ["RT", "R2", "ZR"]),
+            (GamepadButtonType::Select, ["View", "Share", "-"]),
+            (GamepadButtonType::Start, ["Menu", "Options", "+"]),
+            (GamepadButtonType::Mode, ["Xbox", "PS", "Home"]),
+            (GamepadButtonType::LeftThumb, ["LS", "L3", "L Stick"]),
+            (GamepadButtonType::RightThumb, ["RS", "R3", "R Stick"]),
+        ];
+        for (button, names) in buttons.iter() {
+            for (layout, name) in [
+                GamepadLayout::Xbox,
+                GamepadLayout::PlayStation,
+                GamepadLayout::Nintendo,
+            ]
+            .iter()
+            .zip(names.iter())
+            {
+                table.insert_for_layout(
+                    *layout,
+                    InputAxisType::GamepadButton(*button),
+                    name.to_string(),
+                );
+            }
+        }
+        table
+    }
+}
@@ -1,6 +1,6 @@
 static mut NEXT_INPUT_INDEX: u8 = 0;
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 pub struct InputId {
     pub id: u8,
 }
@@ -0,0 +1,591 @@
+use std::{fmt, str::FromStr};
+
+use bevy::{
+    prelude::{GamepadAxisType, GamepadButtonType, KeyCode, MouseButton},
+    utils::HashMap,
+};
+
+use crate::user_input::{InputAxisType, MouseAxisType, MouseWheelDirection, VirtualStickAxis};
+
+const KEY_NAMES: &[(KeyCode, &str)] = &[
+    (KeyCode::Key1, "1"),
+    (KeyCode::Key2, "2"),
+    (KeyCode::Key3, "3"),
+    (KeyCode::Key4, "4"),
+    (KeyCode::Key5, "5"),
+    (KeyCode::Key6, "6"),
+    (KeyCode::Key7, "7"),
+    (KeyCode::Key8, "8"),
+    (KeyCode::Key9, "9"),
+    (KeyCode::Key0, "0"),
+    (KeyCode::A, "A"),
+    (KeyCode::B, "B"),
+    (KeyCode::C, "C"),
+    (KeyCode::D, "D"),
+    (KeyCode::E, "E"),
+    (KeyCode::F, "F"),
+    (KeyCode::G, "G"),
+    (KeyCode::H, "H"),
+    (KeyCode::I, "I"),
+    (KeyCode::J, "J"),
+    (KeyCode::K, "K"),
+    (KeyCode::L, "L"),
+    (KeyCode::M, "M"),
+    (KeyCode::N, "N"),
+    (KeyCode::O, "O"),
+    (KeyCode::P, "P"),
+    (KeyCode::Q, "Q"),
+    (KeyCode::R, "R"),
+    (KeyCode::S, "S"),
+    (KeyCode::T, "T"),
+    (KeyCode::U, "U"),
+    (KeyCode::V, "V"),
+    (KeyCode::W, "W"),
+    (KeyCode::X, "X"),
+    (KeyCode::Y, "Y"),
+    (KeyCode::Z, "Z"),
+    (KeyCode::Escape, "Esc"),
+    (KeyCode::F1, "F1"),
+    (KeyCode::F2, "F2"),
+    (KeyCode::F3, "F3"),
+    (KeyCode::F4, "F4"),
+    (KeyCode::F5, "F5"),
+    (KeyCode::F6, "F6"),
+    (KeyCode::F7, "F7"),
+    (KeyCode::F8, "F8"),
+    (KeyCode::F9, "F9"),
+    (KeyCode::F10, "F10"),
+    (KeyCode::F11, "F11"),
+    (KeyCode::F12, "F12"),
+    (KeyCode::F13, "F13"),
+    (KeyCode::F14, "F14"),
+    (KeyCode::F15, "F15"),
+    (KeyCode::F16, "F16"),
+    (KeyCode::F17, "F17"),
+    (KeyCode::F18, "F18"),
+    (KeyCode::F19, "F19"),
+    (KeyCode::F20, "F20"),
+    (KeyCode::F21, "F21"),
+    (KeyCode::F22, "F22"),
+    (KeyCode::F23, "F23"),
+    (KeyCode::F24, "F24"),
+    (KeyCode::Snapshot, "Print Screen"),
+    (KeyCode::Scroll, "Scroll Lock"),
+    (KeyCode::Pause, "Pause"),
+    (KeyCode::Insert, "Insert"),
+    (KeyCode::Home, "Home"),
+    (KeyCode::Delete, "Delete"),
+    (KeyCode::End, "End"),
+    (KeyCode::PageDown, "Page Down"),
+    (KeyCode::PageUp, "Page Up"),
+    (KeyCode::Left, "Left"),
+    (KeyCode::Up, "Up"),
+    (KeyCode::Right, "Right"),
+    (KeyCode::Down, "Down"),
+    (KeyCode::Back, "Backspace"),
+    (KeyCode::Return, "Enter"),
+    (KeyCode::Space, "Space"),
+    (KeyCode::Compose, "Compose"),
+    (KeyCode::Caret, "Caret"),
+    (KeyCode::Numlock, "Num Lock"),
+    (KeyCode::Numpad0, "Numpad 0"),
+    (KeyCode::Numpad1, "Numpad 1"),
+    (KeyCode::Numpad2, "Numpad 2"),
+    (KeyCode::Numpad3, "Numpad 3"),
+    (KeyCode::Numpad4, "Numpad 4"),
+    (KeyCode::Numpad5, "Numpad 5"),
+    (KeyCode::Numpad6, "Numpad 6"),
+    (KeyCode::Numpad7, "Numpad 7"),
+    (KeyCode::Numpad8, "Numpad 8"),
+    (KeyCode::Numpad9, "Numpad 9"),
+    (KeyCode::AbntC1, "ABNT C1"),
+    (KeyCode::AbntC2, "ABNT C2"),
+    (KeyCode::NumpadAdd, "Numpad Add"),
+    (KeyCode::Apostrophe, "Apostrophe"),
+    (KeyCode::Apps, "Apps"),
+    (KeyCode::Asterisk, "Asterisk"),
+    (KeyCode::Plus, "Plus"),
+    (KeyCode::At, "At"),
+    (KeyCode::Ax, "Ax"),
+    (KeyCode::Backslash, "Backslash"),
+    (KeyCode::Calculator, "Calculator"),
+    (KeyCode::Capital, "Caps Lock"),
+    (KeyCode::Colon, "Colon"),
+    (KeyCode::Comma, "Comma"),
+    (KeyCode::Convert, "Convert"),
+    (KeyCode::NumpadDecimal, "Numpad Decimal"),
+    (KeyCode::NumpadDivide, "Numpad Divide"),
+    (KeyCode::Equals, "Equals"),
+    (KeyCode::Grave, "Grave"),
+    (KeyCode::Kana, "Kana"),
+    (KeyCode::Kanji, "Kanji"),
+    (KeyCode::LAlt, "Alt"),
+    (KeyCode::LBracket, "Left Bracket"),
+    (KeyCode::LControl, "Ctrl"),
+    (KeyCode::LShift, "Shift"),
+    (KeyCode::LWin, "Super"),
+    (KeyCode::Mail, "Mail"),
+    (KeyCode::MediaSelect, "Media Select"),
+    (KeyCode::MediaStop, "Media Stop"),
+    (KeyCode::Minus, "Minus"),
+    (KeyCode::NumpadMultiply, "Numpad Multiply"),
+    (KeyCode::Mute, "Mute"),
+    (KeyCode::MyComputer, "My Computer"),
+    (KeyCode::NavigateForward, "Navigate Forward"),
+    (KeyCode::NavigateBackward, "Navigate Backward"),
+    (KeyCode::NextTrack, "Next Track"),
+    (KeyCode::NoConvert, "No Convert"),
+    (KeyCode::NumpadComma, "Numpad Comma"),
+    (KeyCode::NumpadEnter, "Numpad Enter"),
+    (KeyCode::NumpadEquals, "Numpad Equals"),
+    (KeyCode::Oem102, "OEM 102"),
+    (KeyCode::Period, "Period"),
+    (KeyCode::PlayPause, "Play Pause"),
+    (KeyCode::Power, "Power"),
+    (KeyCode::PrevTrack, "Prev Track"),
+    (KeyCode::RAlt, "Right Alt"),
+    (KeyCode::RBracket, "Right Bracket"),
+    (KeyCode::RControl, "Right Ctrl"),
+    (KeyCode::RShift, "Right Shift"),
+    (KeyCode::RWin, "Right Super"),
+    (KeyCode::Semicolon, "Semicolon"),
+    (KeyCode::Slash, "Slash"),
+    (KeyCode::Sleep, "Sleep"),
+    (KeyCode::Stop, "Stop"),
+    (KeyCode::NumpadSubtract, "Numpad Subtract"),
+    (KeyCode::Sysrq, "SysRq"),
+    (KeyCode::Tab, "Tab"),
+    (KeyCode::Underline, "Underline"),
+    (KeyCode::Unlabeled, "Unlabeled"),
+    (KeyCode::VolumeDown, "Volume Down"),
+    (KeyCode::VolumeUp, "Volume Up"),
+    (KeyCode::Wake, "Wake"),
+    (KeyCode::WebBack, "Web Back"),
+    (KeyCode::WebFavorites, "Web Favorites"),
+    (KeyCode::WebForward, "Web Forward"),
+    (KeyCode::WebHome, "Web Home"),
+    (KeyCode::WebRefresh, "Web Refresh"),
+    (KeyCode::WebSearch, "Web Search"),
+    (KeyCode::WebStop, "Web Stop"),
+    (KeyCode::Yen, "Yen"),
+    (KeyCode::Copy, "Copy"),
+    (KeyCode::Paste, "Paste"),
+    (KeyCode::Cut, "Cut"),
+];
+
+const KEY_ALIASES: &[(KeyCode, &str)] = &[
+    (KeyCode::LControl, "Control"),
+    (KeyCode::LControl, "LCtrl"),
+    (KeyCode::RControl, "RCtrl"),
+    (KeyCode::LWin, "Win"),
+    (KeyCode::LWin, "Cmd"),
+    (KeyCode::Return, "Return"),
+    (KeyCode::Plus, "+"),
+];
+
+// Chords print modifiers first and in this order
+const MODIFIERS: &[KeyCode] = &[
+    KeyCode::LControl,
+    KeyCode::RControl,
+    KeyCode::LShift,
+    KeyCode::RShift,
+    KeyCode::LAlt,
+    KeyCode::RAlt,
+    KeyCode::LWin,
+    KeyCode::RWin,
+];
+
+const MOUSE_BUTTON_NAMES: &[(MouseButton, &str)] = &[
+    (MouseButton::Left, "Left"),
+    (MouseButton::Right, "Right"),
+    (MouseButton::Middle, "Middle"),
+];
+
+const MOUSE_AXIS_NAMES: &[(MouseAxisType, &str)] = &[
+    (MouseAxisType::X, "X"),
+    (MouseAxisType::Y, "Y"),
+    (MouseAxisType::Wheel, "Wheel"),
+    (MouseAxisType::HorizontalWheel, "Horizontal Wheel"),
+];
+
+const MOUSE_WHEEL_NAMES: &[(MouseWheelDirection, &str)] = &[
+    (MouseWheelDirection::Up, "Up"),
+    (MouseWheelDirection::Down, "Down"),
+    (MouseWheelDirection::Left, "Left"),
+    (MouseWheelDirection::Right, "Right"),
+];
+
+const GAMEPAD_BUTTON_NAMES: &[(GamepadButtonType, &str)] = &[
+    (GamepadButtonType::South, "South"),
+    (GamepadButtonType::East, "East"),
+    (GamepadButtonType::North, "North"),
+    (GamepadButtonType::West, "West"),
+    (GamepadButtonType::C, "C"),
+    (GamepadButtonType::Z, "Z"),
+    (GamepadButtonType::LeftTrigger, "Left Trigger"),
+    (GamepadButtonType::LeftTrigger2, "Left Trigger 2"),
+    (GamepadButtonType::RightTrigger, "Right Trigger"),
+    (GamepadButtonType::RightTrigger2, "Right Trigger 2"),
+    (GamepadButtonType::Select, "Select"),
+    (GamepadButtonType::Start, "Start"),
+    (GamepadButtonType::Mode, "Mode"),
+    (GamepadButtonType::LeftThumb, "Left Thumb"),
+    (GamepadButtonType::RightThumb, "Right Thumb"),
+    (GamepadButtonType::DPadUp, "DPad Up"),
+    (GamepadButtonType::DPadDown, "DPad Down"),
+    (GamepadButtonType::DPadLeft, "DPad Left"),
+    (GamepadButtonType::DPadRight, "DPad Right"),
+];
+
+const GAMEPAD_AXIS_NAMES: &[(GamepadAxisType, &str)] = &[
+    (GamepadAxisType::LeftStickX, "Left Stick X"),
+    (GamepadAxisType::LeftStickY, "Left Stick Y"),
+    (GamepadAxisType::LeftZ, "Left Z"),
+    (GamepadAxisType::RightStickX, "Right Stick X"),
+    (GamepadAxisType::RightStickY, "Right Stick Y"),
+    (GamepadAxisType::RightZ, "Right Z"),
+    (GamepadAxisType::DPadX, "DPad X"),
+    (GamepadAxisType::DPadY, "DPad Y"),
+];
+
+const PREFIXES: &[(&str, &str)] = &[
+    ("Key", ""),
+    ("Keyboard", ""),
+    ("ScanCode", "Scan Code "),
+    ("Mouse", "Mouse "),
+    ("MouseAxis", "Mouse Axis "),
+    ("MouseDelta", "Mouse Delta "),
+    ("MouseNormalized", "Mouse Normalized "),
+    ("MouseCentered", "Mouse Centered "),
+    ("Wheel", "Wheel "),
+    ("Gamepad", "Gamepad "),
+    ("GamepadAxis", "Gamepad "),
+    ("GamepadDelta", "Gamepad Delta "),
+    ("Virtual", "Virtual "),
+];
+
+const VIRTUAL_STICK_AXIS_NAMES: &[(VirtualStickAxis, &str)] =
+    &[(VirtualStickAxis::X, "X"), (VirtualStickAxis::Y, "Y")];
+
+fn name_of<T: PartialEq + fmt::Debug>(names: &[(T, &'static str)], value: &T) -> String {
+    names
+        .iter()
+        .find(|(named_value, _)| named_value == value)
+        .map_or_else(|| format!("{:?}", value), |(_, name)| name.to_string())
+}
+
+fn normalize(text: &str) -> String {
+    text.chars()
+        .filter(|c| !c.is_whitespace() && *c != '_')
+        .flat_map(|c| c.to_lowercase())
+        .collect()
+}
+
+impl fmt::Display for InputAxisType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InputAxisType::KeyboardButton(key) => write!(f, "{}", name_of(KEY_NAMES, key)),
+            InputAxisType::KeyboardScanCode(scan_code) => write!(f, "Scan Code {}", scan_code),
+            InputAxisType::MouseButton(MouseButton::Other(button)) => {
+                write!(f, "Mouse {}", button)
+            }
+            InputAxisType::MouseButton(button) => {
+                write!(f, "Mouse {}", name_of(MOUSE_BUTTON_NAMES, button))
+            }
+            InputAxisType::GamepadButton(button) => {
+                write!(f, "Gamepad {}", name_of(GAMEPAD_BUTTON_NAMES, button))
+            }
+            InputAxisType::MouseAxis(axis) => {
+                write!(f, "Mouse Axis {}", name_of(MOUSE_AXIS_NAMES, axis))
+            }
+            InputAxisType::MouseAxisDiff(axis) => {
+                write!(f, "Mouse Delta {}", name_of(MOUSE_AXIS_NAMES, axis))
+            }
+            InputAxisType::MouseAxisNormalized(axis) => {
+                write!(f, "Mouse Normalized {}", name_of(MOUSE_AXIS_NAMES, axis))
+            }
+            InputAxisType::MouseAxisCentered(axis) => {
+                write!(f, "Mouse Centered {}", name_of(MOUSE_AXIS_NAMES, axis))
+            }
+            InputAxisType::MouseWheel(direction) => {
+                write!(f, "Wheel {}", name_of(MOUSE_WHEEL_NAMES, direction))
+            }
+            InputAxisType::GamepadAxis(axis) => {
+                write!(f, "Gamepad {}", name_of(GAMEPAD_AXIS_NAMES, axis))
+            }
+            InputAxisType::GamepadAxisDiff(axis) => {
+                write!(f, "Gamepad Delta {}", name_of(GAMEPAD_AXIS_NAMES, axis))
+            }
+            InputAxisType::VirtualButton(button) => write!(f, "Virtual Button {}", button),
+            InputAxisType::VirtualStick(stick, axis) => write!(
+                f,
+                "Virtual Stick {} {}",
+                stick,
+                name_of(VIRTUAL_STICK_AXIS_NAMES, axis)
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseInputError {
+    pub text: String,
+}
+
+impl fmt::Display for ParseInputError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown input \"{}\"", self.text)
+    }
+}
+
+impl std::error::Error for ParseInputError {}
+
+impl FromStr for InputAxisType {
+    type Err = ParseInputError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let error = || ParseInputError {
+            text: text.to_string(),
+        };
+        let name = match text.split_once(':') {
+            Some((prefix, name)) => {
+                let prefix = normalize(prefix);
+                let (_, display_prefix) = PREFIXES
+                    .iter()
+                    .find(|(config_prefix, _)| normalize(config_prefix) == prefix)
+                    .ok_or_else(error)?;
+                normalize(&format!("{}{}", display_prefix, name))
+            }
+            None => normalize(text),
+        };
+
+        for (key, key_name) in KEY_NAMES.iter().chain(KEY_ALIASES.iter()) {
+            if normalize(key_name) == name || normalize(&format!("{:?}", key)) == name {
+                return Ok(InputAxisType::KeyboardButton(*key));
+            }
+        }
+        if let Some(Ok(scan_code)) = name.strip_prefix("scancode").map(str::parse::<u32>) {
+            return Ok(InputAxisType::KeyboardScanCode(scan_code));
+        }
+        if let Some(Ok(button)) = name.strip_prefix("mouse").map(str::parse::<u16>) {
+            return Ok(InputAxisType::MouseButton(MouseButton::Other(button)));
+        }
+        if let Some(Ok(button)) = name.strip_prefix("virtualbutton").map(str::parse::<u8>) {
+            return Ok(InputAxisType::VirtualButton(button));
+        }
+        if let Some(stick) = name.strip_prefix("virtualstick") {
+            for (axis, axis_name) in VIRTUAL_STICK_AXIS_NAMES {
+                let axis_name = normalize(axis_name);
+                if let Some(Ok(stick)) =
+                    stick.strip_suffix(axis_name.as_str()).map(str::parse::<u8>)
+                {
+                    return Ok(InputAxisType::VirtualStick(stick, *axis));
+                }
+            }
+        }
+        non_keyboard_inputs()
+            .into_iter()
+            .find(|input| normalize(&input.to_string()) == name)
+            .ok_or_else(error)
+    }
+}
+
+fn non_keyboard_inputs() -> Vec<InputAxisType> {
+    let mut inputs = Vec::new();
+    for (button, _) in MOUSE_BUTTON_NAMES {
+        inputs.push(InputAxisType::MouseButton(*button));
+    }
+    for (axis, _) in MOUSE_AXIS_NAMES {
+        inputs.push(InputAxisType::MouseAxis(*axis));
+        inputs.push(InputAxisType::MouseAxisDiff(*axis));
+        inputs.push(InputAxisType::MouseAxisNormalized(*axis));
+        inputs.push(InputAxisType::MouseAxisCentered(*axis));
+    }
+    for (direction, _) in MOUSE_WHEEL_NAMES {
+        inputs.push(InputAxisType::MouseWheel(*direction));
+    }
+    for (button, _) in GAMEPAD_BUTTON_NAMES {
+        inputs.push(InputAxisType::GamepadButton(*button));
+    }
+    for (axis, _) in GAMEPAD_AXIS_NAMES {
+        inputs.push(InputAxisType::GamepadAxis(*axis));
+        inputs.push(InputAxisType::GamepadAxisDiff(*axis));
+    }
+    inputs
+}
+
+pub fn format_chord(keys: &[InputAxisType]) -> String {
+    format_chord_with(keys, |key| key.to_string())
+}
+
+fn format_chord_with(keys: &[InputAxisType], name: impl Fn(&InputAxisType) -> String) -> String {
+    let modifier_index = |key: &InputAxisType| match key {
+        InputAxisType::KeyboardButton(key_code) => {
+            MODIFIERS.iter().position(|modifier| modifier == key_code)
+        }
+        _ => None,
+    };
+    let (mut modifiers, others): (Vec<&InputAxisType>, Vec<&InputAxisType>) =
+        keys.iter().partition(|key| modifier_index(key).is_some());
+    modifiers.sort_by_key(|key| modifier_index(key));
+    modifiers
+        .iter()
+        .chain(others.iter())
+        .map(|key| name(key))
+        .collect::<Vec<String>>()
+        .join(" + ")
+}
+
+// Splits on " + " when present, so names may contain '+'. Compact "Ctrl++" treats
+// '+' at the start of a key as the Plus key
+fn split_chord(text: &str) -> Vec<&str> {
+    if text.contains(" + ") {
+        return text.split(" + ").map(str::trim).collect();
+    }
+    let mut keys = Vec::new();
+    let mut start = 0;
+    for (index, c) in text.char_indices() {
+        if c == '+' && !text[start..index].trim().is_empty() {
+            keys.push(text[start..index].trim());
+            start = index + 1;
+        }
+    }
+    keys.push(text[start..].trim());
+    keys
+}
+
+pub fn parse_chord(text: &str) -> Result<Vec<InputAxisType>, ParseInputError> {
+    split_chord(text).into_iter().map(str::parse).collect()
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct InputNames {
+    names: HashMap<InputAxisType, String>,
+}
+
+impl InputNames {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, input: InputAxisType, name: impl Into<String>) -> &mut Self {
+        self.names.insert(input, name.into());
+        self
+    }
+
+    pub fn get(&self, input: &InputAxisType) -> String {
+        self.names
+            .get(input)
+            .cloned()
+            .unwrap_or_else(|| input.to_string())
+    }
+
+    pub fn format_chord(&self, keys: &[InputAxisType]) -> String {
+        format_chord_with(keys, |key| self.get(key))
+    }
+
+    pub fn parse(&self, text: &str) -> Result<InputAxisType, ParseInputError> {
+        let name = normalize(text);
+        self.names
+            .iter()
+            .find(|(_, input_name)| normalize(input_name) == name)
+            .map(|(input, _)| input.clone())
+            .map_or_else(|| text.parse(), Ok)
+    }
+
+    pub fn parse_chord(&self, text: &str) -> Result<Vec<InputAxisType>, ParseInputError> {
+        split_chord(text)
+            .into_iter()
+            .map(|key| self.parse(key))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::{KeyCode, MouseButton};
+
+    use super::{format_chord, parse_chord, InputNames, KEY_ALIASES, KEY_NAMES};
+    use crate::user_input::{InputAxisType, MouseAxisType};
+
+    #[test]
+    fn key_aliases_are_unique() {
+        for (index, (_, name)) in KEY_ALIASES.iter().enumerate() {
+            assert!(KEY_ALIASES[index + 1..]
+                .iter()
+                .all(|(_, other)| other != name));
+        }
+    }
+
+    #[test]
+    fn mouse_axis_names_differ_from_buttons() {
+        let axis = InputAxisType::MouseAxis(MouseAxisType::X);
+        assert_eq!(axis.to_string(), "Mouse Axis X");
+        assert_eq!("Mouse Axis X".parse(), Ok(axis.clone()));
+        assert_eq!("MouseAxis:X".parse(), Ok(axis));
+        assert!("MouseAxis:Left".parse::<InputAxisType>().is_err());
+        assert_eq!(
+            "Mouse:Left".parse(),
+            Ok(InputAxisType::MouseButton(MouseButton::Left))
+        );
+    }
+
+    #[test]
+    fn chord_round_trip() {
+        let keys = vec![
+            InputAxisType::KeyboardButton(KeyCode::S),
+            InputAxisType::KeyboardButton(KeyCode::LShift),
+            InputAxisType::KeyboardButton(KeyCode::LControl),
+        ];
+        assert_eq!(format_chord(&keys), "Ctrl + Shift + S");
+        assert_eq!(
+            parse_chord("Ctrl+Shift+S"),
+            Ok(vec![
+                InputAxisType::KeyboardButton(KeyCode::LControl),
+                InputAxisType::KeyboardButton(KeyCode::LShift),
+                InputAxisType::KeyboardButton(KeyCode::S),
+            ])
+        );
+    }
+
+    #[test]
+    fn overridden_names_are_formatted_and_parsed() {
+        let mut names = InputNames::new();
+        names.insert(InputAxisType::KeyboardButton(KeyCode::LControl), "Strg");
+        let keys = vec![
+            InputAxisType::KeyboardButton(KeyCode::LControl),
+            InputAxisType::KeyboardButton(KeyCode::S),
+        ];
+        assert_eq!(names.format_chord(&keys), "Strg + S");
+        assert_eq!(names.parse_chord("Strg+S"), Ok(keys.clone()));
+        assert_eq!(names.parse_chord("Ctrl+S"), Ok(keys));
+    }
+
+    #[test]
+    fn chord_with_plus_round_trips() {
+        let keys = vec![
+            InputAxisType::KeyboardButton(KeyCode::LControl),
+            InputAxisType::KeyboardButton(KeyCode::Plus),
+        ];
+        assert_eq!(parse_chord(&format_chord(&keys)), Ok(keys.clone()));
+        assert_eq!(parse_chord("Ctrl++"), Ok(keys.clone()));
+        assert_eq!(parse_chord("Ctrl + +"), Ok(keys));
+
+        let mut names = InputNames::new();
+        names.insert(InputAxisType::KeyboardButton(KeyCode::NumpadAdd), "Num+");
+        let keys = vec![
+            InputAxisType::KeyboardButton(KeyCode::LShift),
+            InputAxisType::KeyboardButton(KeyCode::NumpadAdd),
+        ];
+        assert_eq!(names.format_chord(&keys), "Shift + Num+");
+        assert_eq!(names.parse_chord("Shift + Num+"), Ok(keys));
+    }
+
+    #[test]
+    fn every_key_name_parses_back() {
+        for (key, _) in KEY_NAMES {
+            let input = InputAxisType::KeyboardButton(*key);
+            assert_eq!(input.to_string().parse(), Ok(input));
+        }
+    }
+}
@@ -1,6 +1,16 @@
 pub mod common;
 pub mod config;
+pub mod device;
+pub mod glyph;
 pub mod input_id;
+pub mod input_names;
+pub mod network;
 pub mod plugin;
+pub mod rollback;
+pub mod rumble;
+pub mod scan_code;
 pub mod system;
+pub mod testing;
 pub mod user_input;
+#[cfg(feature = "virtual_controls")]
+pub mod virtual_controls;
@@ -0,0 +1,222 @@
+use std::{
+    collections::VecDeque,
+    convert::TryFrom,
+    sync::{Arc, Mutex},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::input_id::InputId;
+
+// Axis values are sent as fixed point with 1/256 step
+const AXIS_SCALE: f32 = 256.0;
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct InputSnapshot {
+    pub frame: u32,
+    pub pressed: Vec<u8>,
+    pub active_axes: Vec<u8>,
+    pub axis_values: Vec<i32>,
+}
+
+impl InputSnapshot {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(
+            4 + self.pressed.len() + self.active_axes.len() + self.axis_values.len() * 2 + 3,
+        );
+        bytes.extend_from_slice(&self.frame.to_le_bytes());
+        for bits in [&self.pressed, &self.active_axes] {
+            write_varint(&mut bytes, bits.len() as u64);
+            bytes.extend_from_slice(bits);
+        }
+        write_varint(&mut bytes, self.axis_values.len() as u64);
+        for value in self.axis_values.iter() {
+            write_varint(&mut bytes, zigzag(*value));
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut reader = bytes;
+        let mut frame = [0; 4];
+        frame.copy_from_slice(take(&mut reader, 4)?);
+        let pressed = read_bits(&mut reader)?;
+        let active_axes = read_bits(&mut reader)?;
+        let count = read_varint(&mut reader)? as usize;
+        if count > reader.len() {
+            return None;
+        }
+        let mut axis_values = Vec::with_capacity(count);
+        for _ in 0..count {
+            axis_values.push(unzigzag(read_varint(&mut reader)?)?);
+        }
+        if !reader.is_empty() {
+            return None;
+        }
+        Some(Self {
+            frame: u32::from_le_bytes(frame),
+            pressed,
+            active_axes,
+            axis_values,
+        })
+    }
+}
+
+fn take<'a>(reader: &mut &'a [u8], len: usize) -> Option<&'a [u8]> {
+    if reader.len() < len {
+        return None;
+    }
+    let (taken, rest) = reader.split_at(len);
+    *reader = rest;
+    Some(taken)
+}
+
+fn read_bits(reader: &mut &[u8]) -> Option<Vec<u8>> {
+    let len = read_varint(reader)? as usize;
+    take(reader, len).map(<[u8]>::to_vec)
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+fn read_varint(reader: &mut &[u8]) -> Option<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = take(reader, 1)?[0];
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+fn zigzag(value: i32) -> u64 {
+    ((value << 1) ^ (value >> 31)) as u32 as u64
+}
+
+fn unzigzag(value: u64) -> Option<i32> {
+    let value = u32::try_from(value).ok()?;
+    Some((value >> 1) as i32 ^ -((value & 1) as i32))
+}
+
+pub(crate) fn set_bit(bits: &mut Vec<u8>, index: usize) {
+    if bits.len() <= index / 8 {
+        bits.resize(index / 8 + 1, 0);
+    }
+    bits[index / 8] |= 1 << (index % 8);
+}
+
+pub(crate) fn get_bit(bits: &[u8], index: usize) -> bool {
+    bits.get(index / 8)
+        .map_or(false, |byte| byte & (1 << (index % 8)) != 0)
+}
+
+pub(crate) fn quantize_axis(value: f32) -> i32 {
+    (value * AXIS_SCALE)
+        .round()
+        .clamp(i32::MIN as f32, i32::MAX as f32) as i32
+}
+
+pub(crate) fn dequantize_axis(value: i32) -> f32 {
+    value as f32 / AXIS_SCALE
+}
+
+pub trait InputTransport: Send + Sync {
+    fn send(&mut self, input_id: InputId, snapshot: &InputSnapshot);
+    fn receive(&mut self) -> Vec<(InputId, InputSnapshot)>;
+}
+
+#[derive(Clone, Default)]
+pub struct LoopbackTransport {
+    queue: Arc<Mutex<VecDeque<(InputId, Vec<u8>)>>>,
+}
+
+impl LoopbackTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl InputTransport for LoopbackTransport {
+    fn send(&mut self, input_id: InputId, snapshot: &InputSnapshot) {
+        self.queue
+            .lock()
+            .unwrap()
+            .push_back((input_id, snapshot.to_bytes()));
+    }
+
+    fn receive(&mut self) -> Vec<(InputId, InputSnapshot)> {
+        self.queue
+            .lock()
+            .unwrap()
+            .drain(..)
+            .filter_map(|(input_id, bytes)| {
+                InputSnapshot::from_bytes(&bytes).map(|snapshot| (input_id, snapshot))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{dequantize_axis, quantize_axis, InputSnapshot, InputTransport, LoopbackTransport};
+    use crate::input_id::InputId;
+
+    fn snapshot() -> InputSnapshot {
+        InputSnapshot {
+            frame: 70000,
+            pressed: vec![0b101],
+            active_axes: vec![0b11, 0b1],
+            axis_values: [0.5, -1.0, 1919.75]
+                .iter()
+                .map(|value| quantize_axis(*value))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn cursor_positions_are_not_clamped() {
+        for value in [1919.75, -640.5, 0.00390625] {
+            assert_eq!(dequantize_axis(quantize_axis(value)), value);
+        }
+    }
+
+    #[test]
+    fn loopback_round_trip() {
+        let mut transport = LoopbackTransport::new();
+        transport.send(InputId::new(3), &snapshot());
+        transport.send(InputId::new(4), &InputSnapshot::default());
+        let received = transport.receive();
+        assert_eq!(
+            received,
+            vec![
+                (InputId::new(3), snapshot()),
+                (InputId::new(4), InputSnapshot::default())
+            ]
+        );
+        assert!(transport.receive().is_empty());
+    }
+
+    #[test]
+    fn bytes_are_compact() {
+        // 4 frame bytes, 2 + 3 bit set bytes, 1 count byte and 2 + 2 + 3 value bytes
+        assert_eq!(snapshot().to_bytes().len(), 17);
+    }
+
+    #[test]
+    fn malformed_bytes_are_rejected() {
+        let bytes = snapshot().to_bytes();
+        for len in 0..bytes.len() {
+            assert_eq!(InputSnapshot::from_bytes(&bytes[..len]), None);
+        }
+        let mut trailing = bytes;
+        trailing.push(0);
+        assert_eq!(InputSnapshot::from_bytes(&trailing), None);
+    }
+}
@@ -1,9 +1,45 @@
+use std::fmt::Debug;
 use std::hash::Hash;
 use std::marker::PhantomData;
+use std::sync::Mutex;
 
-use super::{system::input_system, user_input::UserInputHandle};
-use bevy::prelude::Plugin;
+#[cfg(feature = "virtual_controls")]
+use super::virtual_controls::virtual_control_system;
+use super::{
+    device::DeviceRouter,
+    system::{fixed_input_system, input_frame_system, input_system, rumble_system},
+    user_input::{InputSourceChanged, UserInputHandle},
+};
+use bevy::app::{AppBuilder, Events};
+use bevy::ecs::component::Component;
+use bevy::ecs::schedule::{IntoRunCriteria, ParallelSystemDescriptor};
+use bevy::input::{
+    keyboard::KeyboardInput,
+    mouse::{MouseButtonInput, MouseMotion, MouseWheel},
+};
+use bevy::prelude::{
+    CoreStage, GamepadEvent, Plugin, StageLabel, SystemLabel, SystemSet, SystemStage,
+};
 use bevy::prelude::{IntoSystem, ParallelSystemDescriptorCoercion};
+use bevy::window::{CursorMoved, ReceivedCharacter, WindowFocused};
+#[derive(Debug, Hash, PartialEq, Eq, Clone, SystemLabel)]
+pub enum InputSystem {
+    FrameStart,
+    RawInput,
+    FixedInput,
+    Rumble,
+    VirtualControls,
+}
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, StageLabel)]
+pub enum InputStage {
+    RawInput,
+}
+
+type AddToStage = Box<dyn Fn(&mut AppBuilder, SystemSet) + Send + Sync>;
+type AddFixedStage = Box<dyn Fn(&mut AppBuilder, bool) + Send + Sync>;
+type AddLabel = Box<dyn Fn(ParallelSystemDescriptor) -> ParallelSystemDescriptor + Send + Sync>;
+
 pub struct InputBindingPlugin<InputType, KeyType>
 where
     InputType: PartialEq + Eq + Hash + Copy + Clone + Send + Sync,
@@ -11,6 +47,10 @@ where
 {
     phantom: PhantomData<InputType>,
     phantom2: PhantomData<KeyType>,
+    stage: Option<AddToStage>,
+    fixed_timestep_stage: Option<AddFixedStage>,
+    input_set: Mutex<Option<SystemSet>>,
+    labels: Vec<AddLabel>,
 }
 
 impl<InputType, KeyType> Default for InputBindingPlugin<InputType, KeyType>
@@ -22,21 +62,241 @@ where
         Self {
             phantom: PhantomData,
             phantom2: PhantomData,
+            stage: None,
+            fixed_timestep_stage: None,
+            input_set: Mutex::new(None),
+            labels: Vec::new(),
         }
     }
 }
 
+impl<InputType: 'static, KeyType: 'static> InputBindingPlugin<InputType, KeyType>
+where
+    InputType: PartialEq + Eq + Hash + Copy + Clone + Send + Sync,
+    KeyType: PartialEq + Eq + Hash + Copy + Clone + Send + Sync,
+{
+    pub fn with_label<L: SystemLabel + Clone>(mut self, label: L) -> Self {
+        self.labels
+            .push(Box::new(move |system| system.label(label.clone())));
+        self
+    }
+
+    pub fn in_stage<S: StageLabel + Clone>(mut self, stage: S) -> Self {
+        self.stage = Some(Box::new(move |app, system_set| {
+            app.add_system_set_to_stage(stage.clone(), system_set);
+        }));
+        self
+    }
+
+    pub fn in_pre_update(self) -> Self {
+        self.in_stage(CoreStage::PreUpdate)
+    }
+
+    pub fn with_fixed_timestep_stage<S: StageLabel + Clone>(mut self, stage: S) -> Self {
+        self.fixed_timestep_stage = Some(Box::new(move |app, add_input_stage| {
+            let has_input_stage = app
+                .app
+                .schedule
+                .get_stage::<SystemStage>(&InputStage::RawInput)
+                .is_some();
+            if add_input_stage && !has_input_stage {
+                app.add_stage_before(stage.clone(), InputStage::RawInput, SystemStage::parallel());
+            }
+            app.add_system_to_stage(
+                stage.clone(),
+                fixed_input_system::<InputType, KeyType>
+                    .system()
+                    .label(InputSystem::FixedInput),
+            );
+        }));
+        self
+    }
+
+    pub fn with_run_criteria<Marker>(self, criteria: impl IntoRunCriteria<Marker>) -> Self {
+        *self.input_set.lock().unwrap() = Some(SystemSet::new().with_run_criteria(criteria));
+        self
+    }
+
+    pub fn run_in_state<T>(self, state: T) -> Self
+    where
+        T: Component + Debug + Clone + Eq + Hash,
+    {
+        *self.input_set.lock().unwrap() = Some(SystemSet::on_update(state));
+        self
+    }
+}
+
 impl<InputType: 'static, KeyType: 'static> Plugin for InputBindingPlugin<InputType, KeyType>
 where
     InputType: PartialEq + Eq + Hash + Copy + Clone + Send + Sync,
     KeyType: PartialEq + Eq + Hash + Copy + Clone + Send + Sync,
 {
     fn build(&self, app: &mut bevy::prelude::AppBuilder) {
+        add_event_if_missing::<KeyboardInput>(app);
+        add_event_if_missing::<CursorMoved>(app);
+        add_event_if_missing::<MouseMotion>(app);
+        add_event_if_missing::<MouseButtonInput>(app);
+        add_event_if_missing::<MouseWheel>(app);
+        add_event_if_missing::<GamepadEvent>(app);
+        add_event_if_missing::<ReceivedCharacter>(app);
+        add_event_if_missing::<WindowFocused>(app);
+        add_event_if_missing::<InputSourceChanged>(app);
+
         app.init_resource::<UserInputHandle<InputType, KeyType>>()
-            .add_system(
-                input_system::<InputType, KeyType>
-                    .system()
-                    .label("raw_input"),
+            .init_resource::<DeviceRouter>();
+
+        let mut input_system = input_system::<InputType, KeyType>
+            .system()
+            .label(InputSystem::RawInput)
+            .label("raw_input");
+        for add_label in self.labels.iter() {
+            input_system = add_label(input_system);
+        }
+        let input_set = self
+            .input_set
+            .lock()
+            .unwrap()
+            .take()
+            .unwrap_or_default()
+            .with_system(input_system);
+        #[cfg(feature = "virtual_controls")]
+        let input_set = input_set.with_system(
+            virtual_control_system::<InputType, KeyType>
+                .system()
+                .label(InputSystem::VirtualControls)
+                .before(InputSystem::RawInput),
+        );
+        let frame_system = input_frame_system::<InputType, KeyType>
+            .system()
+            .label(InputSystem::FrameStart)
+            .before(InputSystem::RawInput);
+        #[cfg(feature = "virtual_controls")]
+        let frame_system = frame_system.before(InputSystem::VirtualControls);
+        let frame_set = SystemSet::new().with_system(frame_system).with_system(
+            rumble_system::<InputType, KeyType>
+                .system()
+                .label(InputSystem::Rumble)
+                .after(InputSystem::RawInput),
+        );
+        if let Some(add_fixed_stage) = &self.fixed_timestep_stage {
+            add_fixed_stage(app, self.stage.is_none());
+        }
+        for system_set in [frame_set, input_set] {
+            match (&self.stage, &self.fixed_timestep_stage) {
+                (Some(add_to_stage), _) => add_to_stage(app, system_set),
+                (None, Some(_)) => {
+                    app.add_system_set_to_stage(InputStage::RawInput, system_set);
+                }
+                (None, None) => {
+                    app.add_system_set(system_set);
+                }
+            }
+        }
+    }
+}
+
+fn add_event_if_missing<T: Component>(app: &mut AppBuilder) {
+    if !app.world().contains_resource::<Events<T>>() {
+        app.add_event::<T>();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{
+        ecs::schedule::ShouldRun,
+        input::ElementState,
+        prelude::{
+            App, AppBuilder, IntoSystem, KeyCode, MinimalPlugins, ParallelSystemDescriptorCoercion,
+            Res, ResMut,
+        },
+    };
+
+    use super::InputBindingPlugin;
+    use crate::{
+        input_id::InputId,
+        testing::{
+            advance_frame,
+            tests::{add_player, handle_mut, test_app, TestBinding, TestHandle, TestInput},
+        },
+        user_input::{InputAxisType, UserInputSet},
+    };
+
+    struct InputEnabled(bool);
+
+    fn input_enabled(enabled: Res<InputEnabled>) -> ShouldRun {
+        if enabled.0 {
+            ShouldRun::Yes
+        } else {
+            ShouldRun::No
+        }
+    }
+
+    #[test]
+    fn disabled_input_releases_held_keys_once() {
+        let mut app = App::build();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(InputEnabled(true))
+            .add_plugin(
+                InputBindingPlugin::<TestInput, TestBinding>::default()
+                    .with_run_criteria(input_enabled.system()),
             );
+        let mut set = UserInputSet::new();
+        set.begin_key(TestBinding::Jump)
+            .add(&[InputAxisType::KeyboardButton(KeyCode::Space)]);
+        let input_id = {
+            let mut handle = handle_mut(&mut app);
+            handle.add_input(TestInput::Main, set);
+            add_player(&mut handle, 0)
+        };
+        let key_state = |app: &mut AppBuilder| {
+            handle_mut(app)
+                .to_handle(&input_id)
+                .unwrap()
+                .get_key_state(TestBinding::Jump)
+        };
+
+        handle_mut(&mut app).press_key(KeyCode::Space);
+        advance_frame(&mut app);
+        assert_eq!(key_state(&mut app), Some(ElementState::Pressed));
+
+        // Press edge ends in the first disabled frame, release is reported in the next one
+        app.world_mut().insert_resource(InputEnabled(false));
+        advance_frame(&mut app);
+        assert_eq!(key_state(&mut app), None);
+        advance_frame(&mut app);
+        assert_eq!(key_state(&mut app), Some(ElementState::Released));
+        advance_frame(&mut app);
+        assert_eq!(key_state(&mut app), None);
+
+        app.world_mut().insert_resource(InputEnabled(true));
+        handle_mut(&mut app).press_key(KeyCode::Space);
+        advance_frame(&mut app);
+        assert_eq!(key_state(&mut app), Some(ElementState::Pressed));
+    }
+
+    struct SeenJump(Option<ElementState>);
+
+    fn read_jump(handle: Res<TestHandle>, mut seen: ResMut<SeenJump>) {
+        seen.0 = handle
+            .to_handle(&InputId::new(0))
+            .and_then(|input_handle| input_handle.get_key_state(TestBinding::Jump));
+    }
+
+    #[test]
+    fn raw_input_string_label_still_orders_systems() {
+        let mut set = UserInputSet::new();
+        set.begin_key(TestBinding::Jump)
+            .add(&[InputAxisType::KeyboardButton(KeyCode::Space)]);
+        let (mut app, _) = test_app(set);
+        app.insert_resource(SeenJump(None))
+            .add_system(read_jump.system().after("raw_input"));
+
+        handle_mut(&mut app).press_key(KeyCode::Space);
+        advance_frame(&mut app);
+        assert_eq!(
+            app.world().get_resource::<SeenJump>().unwrap().0,
+            Some(ElementState::Pressed)
+        );
     }
 }
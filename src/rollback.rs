@@ -0,0 +1,214 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use bevy::{input::ElementState, utils::HashMap};
+
+use crate::{
+    network::InputSnapshot,
+    user_input::{InputAxisType, InputSource},
+};
+
+#[derive(Clone, Default)]
+pub(crate) struct RawInputState {
+    pub(crate) keys: Vec<(HashMap<InputAxisType, ElementState>, usize)>,
+    pub(crate) axes: Vec<Vec<InputAxisType>>,
+    pub(crate) source: Option<InputSource>,
+}
+
+#[derive(Clone)]
+pub(crate) struct SavedFrame {
+    pub(crate) frame: u32,
+    pub(crate) snapshots: HashMap<u8, InputSnapshot>,
+    pub(crate) raw_states: HashMap<u8, RawInputState>,
+    pub(crate) last_source: Option<InputSource>,
+}
+
+pub(crate) struct InputHistory {
+    capacity: usize,
+    frames: VecDeque<SavedFrame>,
+    confirmed: HashMap<u8, BTreeMap<u32, InputSnapshot>>,
+    predicted: HashMap<u8, BTreeMap<u32, InputSnapshot>>,
+}
+
+impl InputHistory {
+    pub(crate) fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        while self.frames.len() > capacity {
+            self.frames.pop_front();
+        }
+    }
+
+    pub(crate) fn save(&mut self, frame: SavedFrame) {
+        if self.capacity == 0 {
+            return;
+        }
+        while self
+            .frames
+            .back()
+            .map_or(false, |saved| saved.frame >= frame.frame)
+        {
+            self.frames.pop_back();
+        }
+        self.frames.push_back(frame);
+        while self.frames.len() > self.capacity {
+            self.frames.pop_front();
+        }
+        self.prune();
+    }
+
+    pub(crate) fn get(&self, frame: u32) -> Option<&SavedFrame> {
+        self.frames.iter().find(|saved| saved.frame == frame)
+    }
+
+    pub(crate) fn add_confirmed(&mut self, input_id: u8, snapshot: InputSnapshot) -> Option<u32> {
+        let frame = snapshot.frame;
+        self.confirmed
+            .entry(input_id)
+            .or_insert_with(BTreeMap::new)
+            .insert(frame, snapshot);
+        let predicted = match self.predicted.get_mut(&input_id) {
+            Some(predicted) => predicted,
+            None => return None,
+        };
+        let confirmed_prediction = predicted.remove(&frame);
+        let confirmed = &self.confirmed[&input_id];
+        let mut mispredicted = confirmed_prediction
+            .filter(|prediction| !same_input(prediction, &confirmed[&frame]))
+            .map(|_| frame);
+        if mispredicted.is_none() {
+            mispredicted = predicted
+                .range(frame..)
+                .find(|(predicted_frame, prediction)| {
+                    let expected = Self::repeat_confirmed(confirmed, **predicted_frame);
+                    !same_input(prediction, &expected)
+                })
+                .map(|(predicted_frame, _)| *predicted_frame);
+        }
+        mispredicted
+    }
+
+    pub(crate) fn get_remote_input(&self, input_id: u8, frame: u32) -> (InputSnapshot, bool) {
+        match self.confirmed.get(&input_id) {
+            Some(confirmed) => match confirmed.get(&frame) {
+                Some(snapshot) => (snapshot.clone(), false),
+                None => (Self::repeat_confirmed(confirmed, frame), true),
+            },
+            None => (
+                InputSnapshot {
+                    frame,
+                    ..Default::default()
+                },
+                true,
+            ),
+        }
+    }
+
+    fn repeat_confirmed(confirmed: &BTreeMap<u32, InputSnapshot>, frame: u32) -> InputSnapshot {
+        let mut prediction = confirmed
+            .range(..=frame)
+            .next_back()
+            .map_or(InputSnapshot::default(), |(_, snapshot)| snapshot.clone());
+        prediction.frame = frame;
+        prediction
+    }
+
+    pub(crate) fn add_prediction(&mut self, input_id: u8, snapshot: InputSnapshot) {
+        self.predicted
+            .entry(input_id)
+            .or_insert_with(BTreeMap::new)
+            .insert(snapshot.frame, snapshot);
+    }
+
+    pub(crate) fn remove_player(&mut self, input_id: u8) {
+        self.confirmed.remove(&input_id);
+        self.predicted.remove(&input_id);
+        for saved in self.frames.iter_mut() {
+            saved.snapshots.remove(&input_id);
+            saved.raw_states.remove(&input_id);
+        }
+    }
+
+    fn prune(&mut self) {
+        let oldest = match self.frames.front() {
+            Some(saved) => saved.frame,
+            None => return,
+        };
+        for confirmed in self.confirmed.values_mut() {
+            let last = confirmed
+                .iter()
+                .next_back()
+                .map(|(frame, snapshot)| (*frame, snapshot.clone()));
+            *confirmed = confirmed.split_off(&oldest);
+            if let Some((frame, snapshot)) = last {
+                confirmed.insert(frame, snapshot);
+            }
+        }
+        for predicted in self.predicted.values_mut() {
+            *predicted = predicted.split_off(&oldest);
+        }
+    }
+}
+
+impl Default for InputHistory {
+    fn default() -> Self {
+        Self {
+            capacity: 0,
+            frames: VecDeque::new(),
+            confirmed: HashMap::default(),
+            predicted: HashMap::default(),
+        }
+    }
+}
+
+fn same_input(left: &InputSnapshot, right: &InputSnapshot) -> bool {
+    left.pressed == right.pressed
+        && left.active_axes == right.active_axes
+        && left.axis_values == right.axis_values
+}
+
+#[cfg(test)]
+mod tests {
+    use super::InputHistory;
+    use crate::network::InputSnapshot;
+
+    fn snapshot(frame: u32, pressed: bool) -> InputSnapshot {
+        InputSnapshot {
+            frame,
+            pressed: vec![pressed as u8],
+            ..Default::default()
+        }
+    }
+
+    fn predict(history: &mut InputHistory, frames: std::ops::RangeInclusive<u32>) {
+        for frame in frames {
+            let (prediction, predicted) = history.get_remote_input(0, frame);
+            assert!(predicted);
+            history.add_prediction(0, prediction);
+        }
+    }
+
+    #[test]
+    fn matching_snapshot_is_not_a_misprediction() {
+        let mut history = InputHistory::default();
+        assert_eq!(history.add_confirmed(0, snapshot(1, true)), None);
+        predict(&mut history, 2..=3);
+        assert_eq!(history.add_confirmed(0, snapshot(2, true)), None);
+        assert_eq!(history.get_remote_input(0, 2), (snapshot(2, true), false));
+    }
+
+    #[test]
+    fn exact_frame_misprediction_is_reported() {
+        let mut history = InputHistory::default();
+        history.add_confirmed(0, snapshot(1, true));
+        predict(&mut history, 2..=3);
+        assert_eq!(history.add_confirmed(0, snapshot(2, false)), Some(2));
+    }
+
+    #[test]
+    fn later_predicted_frames_are_checked() {
+        let mut history = InputHistory::default();
+        history.add_confirmed(0, snapshot(1, true));
+        // Frame 2 was never predicted, frames 3 and 4 repeat frame 1
+        predict(&mut history, 3..=4);
+        assert_eq!(history.add_confirmed(0, snapshot(2, false)), Some(3));
+    }
+}
@@ -0,0 +1,178 @@
+use std::sync::{Arc, Mutex};
+
+use bevy::{prelude::Gamepad, utils::HashMap};
+
+use crate::common::InsertOrGet;
+
+pub trait RumbleBackend: Send + Sync {
+    fn set_motors(&mut self, gamepad: Gamepad, strong: f32, weak: f32);
+}
+
+#[derive(Clone, Default)]
+pub struct InMemoryRumbleBackend {
+    motors: Arc<Mutex<HashMap<Gamepad, (f32, f32)>>>,
+}
+
+impl InMemoryRumbleBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get_motors(&self, gamepad: Gamepad) -> Option<(f32, f32)> {
+        self.motors.lock().unwrap().get(&gamepad).copied()
+    }
+}
+
+impl RumbleBackend for InMemoryRumbleBackend {
+    fn set_motors(&mut self, gamepad: Gamepad, strong: f32, weak: f32) {
+        self.motors.lock().unwrap().insert(gamepad, (strong, weak));
+    }
+}
+
+// Ramp up and ramp down time in seconds
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RumbleEnvelope {
+    pub attack: f32,
+    pub fade: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RumbleRequest {
+    pub strong: f32,
+    pub weak: f32,
+    pub duration: f32,
+    pub envelope: RumbleEnvelope,
+    pub priority: u8,
+}
+
+impl RumbleRequest {
+    pub fn new(strong: f32, weak: f32, duration: f32) -> Self {
+        Self {
+            strong: strong.clamp(0.0, 1.0),
+            weak: weak.clamp(0.0, 1.0),
+            duration,
+            envelope: RumbleEnvelope::default(),
+            priority: 0,
+        }
+    }
+
+    pub fn with_envelope(mut self, attack: f32, fade: f32) -> Self {
+        self.envelope = RumbleEnvelope { attack, fade };
+        self
+    }
+
+    pub fn with_priority(mut self, priority: u8) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    fn envelope_factor(&self, elapsed: f32) -> f32 {
+        let attack = if self.envelope.attack > 0.0 {
+            (elapsed / self.envelope.attack).min(1.0)
+        } else {
+            1.0
+        };
+        let fade = if self.envelope.fade > 0.0 {
+            ((self.duration - elapsed) / self.envelope.fade).min(1.0)
+        } else {
+            1.0
+        };
+        (attack * fade).max(0.0)
+    }
+}
+
+struct ActiveRumble {
+    request: RumbleRequest,
+    elapsed: f32,
+}
+
+// Only effects with the highest priority are played, their motors are mixed by max value
+#[derive(Default)]
+pub(crate) struct RumbleMixer {
+    effects: HashMap<Gamepad, Vec<ActiveRumble>>,
+    motors: HashMap<Gamepad, (f32, f32)>,
+}
+
+impl RumbleMixer {
+    pub(crate) fn add(&mut self, gamepad: Gamepad, request: RumbleRequest) {
+        self.effects.insert_or_get(gamepad).push(ActiveRumble {
+            request,
+            elapsed: 0.0,
+        });
+    }
+
+    pub(crate) fn stop(&mut self, gamepad: Gamepad) {
+        self.effects.remove(&gamepad);
+    }
+
+    pub(crate) fn update(&mut self, delta_seconds: f32) -> Vec<(Gamepad, f32, f32)> {
+        for (_, effects) in self.effects.iter_mut() {
+            for effect in effects.iter_mut() {
+                effect.elapsed += delta_seconds;
+            }
+            effects.retain(|effect| effect.elapsed < effect.request.duration);
+        }
+        self.effects.retain(|_, effects| !effects.is_empty());
+
+        let mut new_motors: HashMap<Gamepad, (f32, f32)> = HashMap::default();
+        for (gamepad, effects) in self.effects.iter() {
+            let priority = effects
+                .iter()
+                .map(|effect| effect.request.priority)
+                .max()
+                .unwrap_or_default();
+            let mut motors = (0.0f32, 0.0f32);
+            for effect in effects
+                .iter()
+                .filter(|effect| effect.request.priority == priority)
+            {
+                let factor = effect.request.envelope_factor(effect.elapsed);
+                motors.0 = motors.0.max(effect.request.strong * factor);
+                motors.1 = motors.1.max(effect.request.weak * factor);
+            }
+            new_motors.insert(*gamepad, motors);
+        }
+
+        let mut changes = Vec::new();
+        for (gamepad, _) in self.motors.iter() {
+            if !new_motors.contains_key(gamepad) {
+                changes.push((*gamepad, 0.0, 0.0));
+            }
+        }
+        for (gamepad, motors) in new_motors.iter() {
+            if self.motors.get(gamepad) != Some(motors) {
+                changes.push((*gamepad, motors.0, motors.1));
+            }
+        }
+        self.motors = new_motors;
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::Gamepad;
+
+    use super::{RumbleMixer, RumbleRequest};
+
+    #[test]
+    fn higher_priority_effect_wins() {
+        let mut mixer = RumbleMixer::default();
+        mixer.add(Gamepad(0), RumbleRequest::new(1.0, 1.0, 1.0));
+        mixer.add(
+            Gamepad(0),
+            RumbleRequest::new(0.2, 0.0, 1.0).with_priority(1),
+        );
+        assert_eq!(mixer.update(0.1), vec![(Gamepad(0), 0.2, 0.0)]);
+        assert_eq!(mixer.update(0.1), vec![]);
+    }
+
+    #[test]
+    fn finished_effect_stops_motors_once() {
+        let mut mixer = RumbleMixer::default();
+        mixer.add(Gamepad(1), RumbleRequest::new(0.5, 0.5, 0.15));
+        assert_eq!(mixer.update(0.1), vec![(Gamepad(1), 0.5, 0.5)]);
+        assert_eq!(mixer.update(0.1), vec![(Gamepad(1), 0.0, 0.0)]);
+        assert_eq!(mixer.update(0.1), vec![]);
+    }
+}
@@ -0,0 +1,39 @@
+// Scan codes of keys at US QWERTY positions
+
+#[cfg(not(target_os = "macos"))]
+mod codes {
+    pub const Q: u32 = 16;
+    pub const W: u32 = 17;
+    pub const E: u32 = 18;
+    pub const R: u32 = 19;
+    pub const A: u32 = 30;
+    pub const S: u32 = 31;
+    pub const D: u32 = 32;
+    pub const F: u32 = 33;
+    pub const Z: u32 = 44;
+    pub const X: u32 = 45;
+    pub const C: u32 = 46;
+    pub const SPACE: u32 = 57;
+    pub const LSHIFT: u32 = 42;
+    pub const LCONTROL: u32 = 29;
+}
+
+#[cfg(target_os = "macos")]
+mod codes {
+    pub const Q: u32 = 12;
+    pub const W: u32 = 13;
+    pub const E: u32 = 14;
+    pub const R: u32 = 15;
+    pub const A: u32 = 0;
+    pub const S: u32 = 1;
+    pub const D: u32 = 2;
+    pub const F: u32 = 3;
+    pub const Z: u32 = 6;
+    pub const X: u32 = 7;
+    pub const C: u32 = 8;
+    pub const SPACE: u32 = 49;
+    pub const LSHIFT: u32 = 56;
+    pub const LCONTROL: u32 = 59;
+}
+
+pub use codes::*;
@@ -6,11 +6,14 @@ use bevy::{
         mouse::{MouseButtonInput, MouseMotion, MouseWheel},
     },
     math::Vec2,
-    prelude::{EventReader, GamepadEvent, ResMut},
-    window::CursorMoved,
+    prelude::{EventReader, EventWriter, GamepadEvent, Res, ResMut, Time},
+    window::{CursorMoved, ReceivedCharacter, WindowFocused, Windows},
 };
 
-use super::user_input::UserInputHandle;
+use super::{
+    device::{DeviceRouter, InputDevice, InputDomain},
+    user_input::{InputSourceChanged, UserInputHandle},
+};
 
 //TODO: add touch support with gestures
 #[allow(clippy::too_many_arguments)]
@@ -26,33 +29,81 @@ pub(crate) fn input_system<InputType: 'static, KeyType: 'static>(
     mut evr_scroll: EventReader<MouseWheel>,
 
     mut evr_gamepad: EventReader<GamepadEvent>,
+
+    mut evr_characters: EventReader<ReceivedCharacter>,
+
+    mut evr_focus: EventReader<WindowFocused>,
+    time: Res<Time>,
+    windows: Option<Res<Windows>>,
+    mut evw_source: EventWriter<InputSourceChanged>,
+    router: Res<DeviceRouter>,
     mut user_input: ResMut<UserInputHandle<InputType, KeyType>>,
 ) where
     InputType: PartialEq + Eq + Hash + Copy + Clone + Send + Sync,
     KeyType: PartialEq + Eq + Hash + Copy + Clone + Send + Sync,
 {
-    user_input.finish_processing();
+    let domain = InputDomain::of::<InputType, KeyType>();
+    let keyboard = router.accepts(InputDevice::Keyboard, domain);
+    let mouse = router.accepts(InputDevice::Mouse, domain);
+
+    user_input.mark_processed();
+    user_input.set_blocked_devices(router.get_blocked(domain));
+    if let Some(windows) = windows {
+        user_input.retain_window_sizes(|window| windows.get(window).is_some());
+        for window in windows.iter() {
+            user_input.set_window_size(window.id(), Vec2::new(window.width(), window.height()));
+        }
+    }
+    user_input.process_injected_inputs();
+
+    for ev in evr_focus.iter() {
+        user_input.process_window_focus(ev.focused);
+    }
+
     // Keyboard input
     for ev in evr_keys.iter() {
-        let state = ev.state;
-        if let Some(key_code) = ev.key_code {
-            user_input.process_keyboard_key(key_code, state);
+        if !keyboard {
+            continue;
         }
+        user_input.process_keyboard_event(ev.key_code, Some(ev.scan_code), ev.state);
+    }
+
+    for ev in evr_characters.iter() {
+        if !keyboard {
+            continue;
+        }
+        user_input.process_received_character(ev.char);
     }
 
     // Absolute cursor position (in window coordinates)
-    for (abs_position, delta_position) in evr_cursor.iter().zip(evr_motion.iter()) {
-        user_input.process_mouse(abs_position.position, delta_position.delta);
+    for ev in evr_cursor.iter() {
+        if !mouse {
+            continue;
+        }
+        user_input.process_mouse_position(ev.id, ev.position);
+    }
+
+    for ev in evr_motion.iter() {
+        if !mouse {
+            continue;
+        }
+        user_input.process_mouse_motion(ev.delta);
     }
 
     // Mouse buttons
     for ev in evr_mousebtn.iter() {
+        if !mouse {
+            continue;
+        }
         user_input.process_mouse_button(ev.button, ev.state);
     }
 
     // scrolling (mouse wheel, touchpad, etc.)
     for ev in evr_scroll.iter() {
-        user_input.process_mouse_wheel(Vec2::new(ev.x, ev.y));
+        if !mouse {
+            continue;
+        }
+        user_input.process_mouse_wheel(Vec2::new(ev.x, ev.y), ev.unit);
     }
 
     // for ev in evr_touch.iter() {
@@ -61,6 +112,94 @@ pub(crate) fn input_system<InputType: 'static, KeyType: 'static>(
 
     //Gamepad input
     for ev_gmp in evr_gamepad.iter() {
+        if !router.accepts(InputDevice::Gamepad(ev_gmp.0), domain) {
+            continue;
+        }
         user_input.process_gamepad(ev_gmp.0, ev_gmp.1.clone());
     }
+
+    user_input.flush_mouse_motion();
+
+    user_input.update_time(time.delta_seconds());
+
+    for ev in user_input.take_input_source_changes() {
+        evw_source.send(ev);
+    }
+}
+
+pub(crate) fn input_frame_system<InputType: 'static, KeyType: 'static>(
+    mut user_input: ResMut<UserInputHandle<InputType, KeyType>>,
+) where
+    InputType: PartialEq + Eq + Hash + Copy + Clone + Send + Sync,
+    KeyType: PartialEq + Eq + Hash + Copy + Clone + Send + Sync,
+{
+    user_input.begin_frame();
+}
+
+pub(crate) fn rumble_system<InputType: 'static, KeyType: 'static>(
+    time: Res<Time>,
+    mut user_input: ResMut<UserInputHandle<InputType, KeyType>>,
+) where
+    InputType: PartialEq + Eq + Hash + Copy + Clone + Send + Sync,
+    KeyType: PartialEq + Eq + Hash + Copy + Clone + Send + Sync,
+{
+    user_input.update_rumble(time.delta_seconds());
+}
+
+pub(crate) fn fixed_input_system<InputType: 'static, KeyType: 'static>(
+    mut user_input: ResMut<UserInputHandle<InputType, KeyType>>,
+) where
+    InputType: PartialEq + Eq + Hash + Copy + Clone + Send + Sync,
+    KeyType: PartialEq + Eq + Hash + Copy + Clone + Send + Sync,
+{
+    user_input.begin_fixed_tick();
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{
+        app::Events,
+        input::mouse::MouseMotion,
+        math::Vec2,
+        window::{CursorMoved, WindowId},
+    };
+
+    use crate::{
+        testing::{
+            advance_frame,
+            tests::{handle_mut, test_app, TestBinding},
+        },
+        user_input::{InputAxisType, MouseAxisType, UserInputSet},
+    };
+
+    #[test]
+    fn uneven_motion_and_cursor_events_are_all_processed() {
+        let mut set = UserInputSet::new();
+        set.begin_axis(TestBinding::MoveX)
+            .add(InputAxisType::MouseAxisDiff(MouseAxisType::X));
+        set.begin_axis(TestBinding::Look)
+            .add(InputAxisType::MouseAxis(MouseAxisType::X));
+        let (mut app, input_id) = test_app(set);
+
+        let world = app.world_mut();
+        let mut motion = world.get_resource_mut::<Events<MouseMotion>>().unwrap();
+        for x in [1.0, 2.0, 4.0].iter() {
+            motion.send(MouseMotion {
+                delta: Vec2::new(*x, 0.0),
+            });
+        }
+        let mut cursor = world.get_resource_mut::<Events<CursorMoved>>().unwrap();
+        for x in [10.0, 40.0].iter() {
+            cursor.send(CursorMoved {
+                id: WindowId::primary(),
+                position: Vec2::new(*x, 5.0),
+            });
+        }
+        advance_frame(&mut app);
+
+        let handle = handle_mut(&mut app);
+        let input_handle = handle.to_handle(&input_id).unwrap();
+        assert_eq!(input_handle.get_axis_value(TestBinding::MoveX), Some(7.0));
+        assert_eq!(input_handle.get_axis_value(TestBinding::Look), Some(40.0));
+    }
 }
@@ -0,0 +1,153 @@
+use std::hash::Hash;
+
+use bevy::prelude::{App, AppBuilder, MinimalPlugins};
+
+use crate::plugin::InputBindingPlugin;
+
+pub fn headless_app<InputType: 'static, KeyType: 'static>() -> AppBuilder
+where
+    InputType: PartialEq + Eq + Hash + Copy + Clone + Send + Sync,
+    KeyType: PartialEq + Eq + Hash + Copy + Clone + Send + Sync,
+{
+    let mut app = App::build();
+    app.add_plugins(MinimalPlugins)
+        .add_plugin(InputBindingPlugin::<InputType, KeyType>::default());
+    app
+}
+
+pub fn advance_frame(app: &mut AppBuilder) {
+    app.app.update();
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use bevy::{
+        input::ElementState,
+        prelude::{AppBuilder, Gamepad, GamepadAxisType, KeyCode, Mut},
+    };
+
+    use super::{advance_frame, headless_app};
+    use crate::{
+        input_id::InputId,
+        user_input::{InputAxisType, UserInputHandle, UserInputSet},
+    };
+
+    #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord)]
+    pub(crate) enum TestInput {
+        Main,
+        Other,
+    }
+
+    #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord)]
+    pub(crate) enum TestBinding {
+        Jump,
+        MoveX,
+        Look,
+    }
+
+    pub(crate) type TestHandle = UserInputHandle<TestInput, TestBinding>;
+
+    // Ids are set explicitly, so gamepad index routing is predictable
+    pub(crate) fn add_player(handle: &mut TestHandle, id: u8) -> InputId {
+        let input_id = InputId::new(id);
+        handle.switch_input(&input_id, TestInput::Main);
+        input_id
+    }
+
+    // Handle with `set` and InputId 0 using it
+    pub(crate) fn test_handle(set: UserInputSet<TestBinding>) -> (TestHandle, InputId) {
+        let mut handle = TestHandle::new();
+        handle.add_input(TestInput::Main, set);
+        let input_id = add_player(&mut handle, 0);
+        (handle, input_id)
+    }
+
+    pub(crate) fn test_app(set: UserInputSet<TestBinding>) -> (AppBuilder, InputId) {
+        let mut app = headless_app::<TestInput, TestBinding>();
+        let input_id = {
+            let mut handle = handle_mut(&mut app);
+            handle.add_input(TestInput::Main, set);
+            add_player(&mut handle, 0)
+        };
+        (app, input_id)
+    }
+
+    pub(crate) fn handle_mut(app: &mut AppBuilder) -> Mut<'_, TestHandle> {
+        app.world_mut().get_resource_mut::<TestHandle>().unwrap()
+    }
+
+    fn key_state(app: &mut AppBuilder, input_id: &InputId) -> Option<ElementState> {
+        app.world()
+            .get_resource::<TestHandle>()
+            .unwrap()
+            .to_handle(input_id)
+            .unwrap()
+            .get_key_state(TestBinding::Jump)
+    }
+
+    fn axis_value(app: &mut AppBuilder, input_id: &InputId) -> Option<f32> {
+        app.world()
+            .get_resource::<TestHandle>()
+            .unwrap()
+            .to_handle(input_id)
+            .unwrap()
+            .get_axis_value(TestBinding::MoveX)
+    }
+
+    fn jump_on_space() -> UserInputSet<TestBinding> {
+        let mut set = UserInputSet::new();
+        set.begin_key(TestBinding::Jump)
+            .add(&[InputAxisType::KeyboardButton(KeyCode::Space)]);
+        set.begin_axis(TestBinding::MoveX)
+            .add(InputAxisType::GamepadAxis(GamepadAxisType::LeftStickX));
+        set
+    }
+
+    #[test]
+    fn press_hold_release() {
+        let (mut app, input_id) = test_app(jump_on_space());
+
+        handle_mut(&mut app).press_key(KeyCode::Space);
+        advance_frame(&mut app);
+        assert_eq!(key_state(&mut app, &input_id), Some(ElementState::Pressed));
+
+        advance_frame(&mut app);
+        assert_eq!(key_state(&mut app, &input_id), None);
+
+        handle_mut(&mut app).release_key(KeyCode::Space);
+        advance_frame(&mut app);
+        assert_eq!(key_state(&mut app, &input_id), Some(ElementState::Released));
+
+        advance_frame(&mut app);
+        assert_eq!(key_state(&mut app, &input_id), None);
+    }
+
+    #[test]
+    fn inject_input_reaches_only_its_input_id() {
+        let (mut app, first) = test_app(jump_on_space());
+        let second = add_player(&mut handle_mut(&mut app), 1);
+
+        handle_mut(&mut app).inject_input(
+            &second,
+            InputAxisType::KeyboardButton(KeyCode::Space),
+            ElementState::Pressed,
+            None,
+        );
+        advance_frame(&mut app);
+        assert_eq!(key_state(&mut app, &first), None);
+        assert_eq!(key_state(&mut app, &second), Some(ElementState::Pressed));
+    }
+
+    #[test]
+    fn gamepad_axis_is_injected() {
+        let (mut app, input_id) = test_app(jump_on_space());
+
+        handle_mut(&mut app).set_gamepad_axis(Gamepad(0), GamepadAxisType::LeftStickX, 0.5);
+        advance_frame(&mut app);
+        assert_eq!(axis_value(&mut app, &input_id), Some(0.5));
+
+        handle_mut(&mut app).set_gamepad_axis(Gamepad(0), GamepadAxisType::LeftStickX, 0.0);
+        advance_frame(&mut app);
+        assert_eq!(axis_value(&mut app, &input_id), None);
+    }
+}
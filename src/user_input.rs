@@ -1,19 +1,29 @@
 use std::{
     cell::Ref,
-    collections::hash_map::{Entry, Keys},
+    collections::{hash_map::Entry, VecDeque},
     hash::Hash,
 };
 
 use bevy::{
-    input::ElementState,
-    math::Vec2,
+    input::{mouse::MouseScrollUnit, ElementState},
+    math::{Mat4, Vec2, Vec3},
     prelude::{
         Gamepad, GamepadAxisType, GamepadButtonType, GamepadEventType, KeyCode, MouseButton,
     },
+    transform::components::GlobalTransform,
     utils::{HashMap, HashSet},
+    window::WindowId,
 };
 
-use crate::{common::InsertOrGet, config::InputConfig};
+use crate::{
+    common::InsertOrGet,
+    config::InputConfig,
+    device::{DeviceClaims, InputDevice},
+    glyph::{GamepadLayout, GlyphTable},
+    network::{dequantize_axis, get_bit, quantize_axis, set_bit, InputSnapshot},
+    rollback::{InputHistory, RawInputState, SavedFrame},
+    rumble::{RumbleBackend, RumbleMixer, RumbleRequest},
+};
 
 use super::input_id::InputId;
 use serde::{Deserialize, Serialize};
@@ -39,6 +49,19 @@ pub struct InputKeyset {
     pub(crate) keys_state: HashMap<InputAxisType, ElementState>,
     pub(crate) repeat_all_for_activate: bool,
     pub(crate) default_keys: Vec<InputAxisType>,
+    pub(crate) keys: Vec<InputAxisType>,
+    pub(crate) axis_buttons: Vec<Option<AxisButton>>,
+    pub(crate) repeat: Option<KeyRepeat>,
+    pub(crate) held_time: f32,
+    pub(crate) next_repeat_time: f32,
+    pub(crate) repeated: bool,
+    pub(crate) toggle: bool,
+    pub(crate) toggled: bool,
+    pub(crate) buffer_window: Option<f32>,
+    pub(crate) buffered_time: Option<f32>,
+    pub(crate) pending_pressed: bool,
+    pub(crate) pending_released: bool,
+    pub(crate) fixed_state: Option<ElementState>,
 }
 
 impl InputKeyset {
@@ -48,7 +71,20 @@ impl InputKeyset {
             activated_keys_num: 0,
             keys_state: HashMap::default(),
             repeat_all_for_activate,
+            keys: keyset.clone(),
+            axis_buttons: vec![None; keyset.len()],
             default_keys: keyset,
+            repeat: None,
+            held_time: 0.0,
+            next_repeat_time: 0.0,
+            repeated: false,
+            toggle: false,
+            toggled: false,
+            buffer_window: None,
+            buffered_time: None,
+            pending_pressed: false,
+            pending_released: false,
+            fixed_state: None,
         };
         set.reset_to_default();
         set
@@ -81,7 +117,29 @@ impl InputKeyset {
         }
     }
 
+    pub fn update_key_value(
+        &mut self,
+        key: InputAxisType,
+        default_state: ElementState,
+        value: f32,
+    ) {
+        let axis_button = self
+            .keys
+            .iter()
+            .position(|elem| *elem == key)
+            .and_then(|index| self.axis_buttons.get(index).copied().flatten());
+        let new_state = match axis_button {
+            Some(axis_button) => {
+                let pressed = self.keys_state.get(&key) == Some(&ElementState::Pressed);
+                axis_button.get_state(pressed, value)
+            }
+            None => default_state,
+        };
+        self.update_key_state(key, new_state);
+    }
+
     pub(crate) fn update_state(&mut self) {
+        self.repeated = false;
         match self.state {
             InputState::Released => {}
             InputState::ShouldBeActivated => {
@@ -94,11 +152,143 @@ impl InputKeyset {
         }
     }
 
+    pub(crate) fn update_repeat(&mut self, delta_seconds: f32) {
+        let repeat = match self.repeat {
+            Some(repeat) => repeat,
+            None => return,
+        };
+        match self.state {
+            InputState::ShouldBeActivated => {
+                self.held_time = 0.0;
+                self.next_repeat_time = repeat.delay;
+            }
+            InputState::Pressed => {
+                self.held_time += delta_seconds;
+                if self.held_time >= self.next_repeat_time {
+                    self.repeated = true;
+                    self.next_repeat_time += repeat.interval;
+                }
+            }
+            InputState::Released | InputState::ShouldBeDeactvated => {
+                self.held_time = 0.0;
+            }
+        }
+    }
+
+    pub(crate) fn update_toggle(&mut self) {
+        if self.toggle && self.state == InputState::ShouldBeActivated {
+            self.toggled = !self.toggled;
+        }
+    }
+
+    pub(crate) fn update_buffer(&mut self, delta_seconds: f32) {
+        let window = match self.buffer_window {
+            Some(window) => window,
+            None => return,
+        };
+        if self.is_just_pressed() {
+            self.buffered_time = Some(window);
+        } else if let Some(time) = self.buffered_time {
+            let time = time - delta_seconds;
+            self.buffered_time = if time > 0.0 { Some(time) } else { None };
+        }
+    }
+
+    pub(crate) fn accumulate_fixed(&mut self) {
+        if self.is_just_pressed() {
+            self.pending_pressed = true;
+        }
+        if self.state == InputState::ShouldBeDeactvated {
+            self.pending_released = true;
+        }
+    }
+
+    pub(crate) fn begin_fixed_tick(&mut self) {
+        self.fixed_state = if self.pending_pressed {
+            self.pending_pressed = false;
+            Some(ElementState::Pressed)
+        } else if self.pending_released {
+            self.pending_released = false;
+            Some(ElementState::Released)
+        } else {
+            None
+        };
+    }
+
+    pub(crate) fn is_held(&self) -> bool {
+        self.state == InputState::ShouldBeActivated || self.state == InputState::Pressed
+    }
+
+    pub(crate) fn apply_remote(&mut self, pressed: bool) {
+        if pressed && !self.is_held() {
+            self.state = InputState::ShouldBeActivated;
+        } else if !pressed && self.is_held() {
+            self.state = InputState::ShouldBeDeactvated;
+        }
+    }
+
+    pub(crate) fn is_active(&self) -> bool {
+        if self.toggle {
+            self.toggled
+        } else {
+            self.is_held()
+        }
+    }
+
+    pub(crate) fn is_just_pressed(&self) -> bool {
+        self.state == InputState::ShouldBeActivated || self.repeated
+    }
+
+    pub(crate) fn get_edge_state(&self) -> Option<ElementState> {
+        if self.is_just_pressed() {
+            Some(ElementState::Pressed)
+        } else if self.state == InputState::ShouldBeDeactvated {
+            Some(ElementState::Released)
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn release_all(&mut self) {
+        let pressed_keys: Vec<InputAxisType> = self
+            .keys_state
+            .iter()
+            .filter(|(_, state)| **state == ElementState::Pressed)
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in pressed_keys {
+            self.update_key_state(key, ElementState::Released);
+        }
+        if self.state == InputState::ShouldBeActivated {
+            self.state = InputState::Released;
+        }
+        self.buffered_time = None;
+    }
+
+    pub(crate) fn release_inputs(&mut self, filter: &impl Fn(&InputAxisType) -> bool) {
+        let pressed_keys: Vec<InputAxisType> = self
+            .keys_state
+            .iter()
+            .filter(|(key, state)| **state == ElementState::Pressed && filter(key))
+            .map(|(key, _)| key.clone())
+            .collect();
+        if pressed_keys.is_empty() {
+            return;
+        }
+        for key in pressed_keys {
+            self.update_key_state(key, ElementState::Released);
+        }
+        if self.state == InputState::ShouldBeActivated {
+            self.state = InputState::Released;
+        }
+    }
+
     pub(crate) fn reset_to_default(&mut self) {
         self.keys_state.clear();
         for key in self.default_keys.iter() {
             self.keys_state.insert(key.clone(), ElementState::Released);
         }
+        self.keys = self.default_keys.clone();
     }
 
     pub(crate) fn apply_rebind(&mut self, rebind: &HashMap<InputAxisType, InputAxisType>) {
@@ -108,6 +298,11 @@ impl InputKeyset {
                 self.keys_state.insert(val.clone(), key_state);
             }
         }
+        for key in self.keys.iter_mut() {
+            if let Some(val) = rebind.get(key) {
+                *key = val.clone();
+            }
+        }
     }
 }
 
@@ -117,23 +312,245 @@ impl Default for InputKeyset {
     }
 }
 
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug, Deserialize, Serialize)]
+pub enum AxisDirection {
+    Positive,
+    Negative,
+}
+
+#[derive(PartialEq, Clone, Copy, Debug, Deserialize, Serialize)]
+pub struct AxisButton {
+    pub direction: AxisDirection,
+    pub press_threshold: f32,
+    pub release_threshold: f32,
+}
+
+impl AxisButton {
+    pub(crate) fn get_state(&self, pressed: bool, value: f32) -> ElementState {
+        let value = match self.direction {
+            AxisDirection::Positive => value,
+            AxisDirection::Negative => -value,
+        };
+        let threshold = if pressed {
+            self.release_threshold
+        } else {
+            self.press_threshold
+        };
+        if value >= threshold {
+            ElementState::Pressed
+        } else {
+            ElementState::Released
+        }
+    }
+}
+
+#[derive(PartialEq, Clone, Copy, Debug, Deserialize, Serialize)]
+pub struct AxisRamp {
+    pub ramp_up: f32,
+    pub ramp_down: f32,
+}
+
+#[derive(PartialEq, Clone, Copy, Debug, Deserialize, Serialize)]
+pub struct KeyRepeat {
+    pub delay: f32,
+    pub interval: f32,
+}
+
+#[derive(PartialEq, Clone, Debug, Deserialize, Serialize)]
+pub struct ActionMetadata {
+    pub display_name: String,
+    pub description: String,
+    pub category: String,
+    pub rebindable: bool,
+}
+
+impl Default for ActionMetadata {
+    fn default() -> Self {
+        Self {
+            display_name: String::new(),
+            description: String::new(),
+            category: String::new(),
+            rebindable: true,
+        }
+    }
+}
+
+impl ActionMetadata {
+    pub fn new(display_name: &str) -> Self {
+        Self {
+            display_name: display_name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_description(mut self, description: &str) -> Self {
+        self.description = description.to_string();
+        self
+    }
+
+    pub fn with_category(mut self, category: &str) -> Self {
+        self.category = category.to_string();
+        self
+    }
+
+    pub fn with_rebindable(mut self, rebindable: bool) -> Self {
+        self.rebindable = rebindable;
+        self
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct BindingInfo<'a, Key> {
+    pub name: Key,
+    pub metadata: &'a ActionMetadata,
+    pub keys: Vec<InputAxisType>,
+}
+
+#[derive(Clone, Copy)]
+pub struct BindingView<'a, Key> {
+    pub name: Key,
+    pub metadata: Option<&'a ActionMetadata>,
+    keyset: Option<&'a InputKeyset>,
+    axisset: Option<&'a InputAxisSet>,
+}
+
+impl<Key> BindingView<'_, Key> {
+    pub fn is_keyset(&self) -> bool {
+        self.keyset.is_some()
+    }
+
+    pub fn is_axis(&self) -> bool {
+        self.axisset.is_some()
+    }
+
+    pub fn get_default_keys(&self) -> Vec<InputAxisType> {
+        let mut keys = Vec::new();
+        if let Some(keyset) = self.keyset {
+            keys.extend(keyset.default_keys.iter().cloned());
+        }
+        if let Some(axisset) = self.axisset {
+            keys.extend(axisset.get_axises().cloned());
+        }
+        keys
+    }
+
+    pub fn get_keys(&self) -> Vec<InputAxisType> {
+        let mut keys = Vec::new();
+        if let Some(keyset) = self.keyset {
+            keys.extend(keyset.keys.iter().cloned());
+        }
+        if let Some(axisset) = self.axisset {
+            keys.extend(axisset.keys.iter().cloned());
+        }
+        keys
+    }
+
+    pub fn get_axis_multipliers(&self) -> Vec<(InputAxisType, f32)> {
+        self.axisset.map_or(Vec::new(), |axisset| {
+            axisset
+                .axises
+                .iter()
+                .map(|(axis, value)| (axis.clone(), *value))
+                .collect()
+        })
+    }
+
+    pub fn get_key_state(&self) -> Option<ElementState> {
+        self.keyset.and_then(|keyset| keyset.get_edge_state())
+    }
+
+    pub fn is_held(&self) -> bool {
+        self.keyset.map_or(false, |keyset| keyset.is_held())
+    }
+
+    pub fn is_toggled(&self) -> Option<bool> {
+        self.keyset
+            .filter(|keyset| keyset.toggle)
+            .map(|keyset| keyset.toggled)
+    }
+
+    pub fn get_axis_value(&self) -> Option<f32> {
+        self.axisset.and_then(|axisset| axisset.get_value())
+    }
+
+    pub fn get_active_axises(&self) -> Vec<InputAxisType> {
+        self.axisset
+            .map_or(Vec::new(), |axisset| axisset.active_axis_types.clone())
+    }
+}
+
 #[derive(PartialEq, Eq, Hash, Clone, Copy, Debug, Deserialize, Serialize)]
 pub enum MouseAxisType {
     X,
     Y,
     Wheel,
+    HorizontalWheel,
+}
+
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug, Deserialize, Serialize)]
+pub enum MouseWheelDirection {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug, Deserialize, Serialize)]
+pub enum VirtualStickAxis {
+    X,
+    Y,
 }
 
 #[derive(PartialEq, Eq, Hash, Clone, Debug, Deserialize, Serialize)]
-#[allow(dead_code)]
 pub enum InputAxisType {
     KeyboardButton(KeyCode),
+    KeyboardScanCode(u32),
     MouseButton(MouseButton),
     GamepadButton(GamepadButtonType),
     MouseAxis(MouseAxisType),
     MouseAxisDiff(MouseAxisType),
+    // Cursor position in 0..1 of the window size
+    MouseAxisNormalized(MouseAxisType),
+    // Cursor position in -1..1 with zero at the window center
+    MouseAxisCentered(MouseAxisType),
+    MouseWheel(MouseWheelDirection),
     GamepadAxis(GamepadAxisType),
     GamepadAxisDiff(GamepadAxisType),
+    VirtualButton(u8),
+    VirtualStick(u8, VirtualStickAxis),
+}
+
+impl InputAxisType {
+    pub fn input_source(&self) -> InputSource {
+        match self {
+            InputAxisType::KeyboardButton(_) | InputAxisType::KeyboardScanCode(_) => {
+                InputSource::Keyboard
+            }
+            InputAxisType::MouseButton(_)
+            | InputAxisType::MouseAxis(_)
+            | InputAxisType::MouseAxisDiff(_)
+            | InputAxisType::MouseAxisNormalized(_)
+            | InputAxisType::MouseAxisCentered(_)
+            | InputAxisType::MouseWheel(_) => InputSource::Mouse,
+            InputAxisType::GamepadButton(_)
+            | InputAxisType::GamepadAxis(_)
+            | InputAxisType::GamepadAxisDiff(_) => InputSource::Gamepad,
+            InputAxisType::VirtualButton(_) | InputAxisType::VirtualStick(_, _) => {
+                InputSource::Virtual
+            }
+        }
+    }
+
+    pub fn is_delta(&self) -> bool {
+        matches!(
+            self,
+            InputAxisType::MouseAxisDiff(_)
+                | InputAxisType::GamepadAxisDiff(_)
+                | InputAxisType::MouseAxis(MouseAxisType::Wheel)
+                | InputAxisType::MouseAxis(MouseAxisType::HorizontalWheel)
+                | InputAxisType::MouseWheel(_)
+        )
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -146,16 +563,40 @@ pub struct InputAxisSet {
     #[serde(skip_serializing, skip_deserializing)]
     pub(crate) out_value: Option<f32>,
     pub(crate) default_axises: HashMap<InputAxisType, f32>,
+    pub(crate) ramp: Option<AxisRamp>,
+    #[serde(skip_serializing, skip_deserializing)]
+    pub(crate) ramped_value: f32,
+    #[serde(skip_serializing, skip_deserializing)]
+    pub(crate) digital_value: bool,
+    #[serde(skip_serializing, skip_deserializing)]
+    pub(crate) pending_delta: f32,
+    #[serde(skip_serializing, skip_deserializing)]
+    pub(crate) fixed_delta: f32,
+    #[serde(skip_serializing, skip_deserializing)]
+    pub(crate) fixed_value: Option<f32>,
+    #[serde(default)]
+    pub(crate) default_keys: Vec<InputAxisType>,
+    #[serde(default)]
+    pub(crate) keys: Vec<InputAxisType>,
 }
 
 impl InputAxisSet {
     pub fn new(axises: HashMap<InputAxisType, f32>) -> Self {
+        let keys: Vec<InputAxisType> = axises.keys().cloned().collect();
         Self {
             state: InputState::Released,
             axises: axises.clone(),
             active_axis_types: Vec::new(),
             out_value: None,
             default_axises: axises,
+            ramp: None,
+            ramped_value: 0.0,
+            digital_value: false,
+            pending_delta: 0.0,
+            fixed_delta: 0.0,
+            fixed_value: None,
+            default_keys: keys.clone(),
+            keys,
         }
     }
     // #[allow(dead_code)]
@@ -189,6 +630,7 @@ impl InputAxisSet {
                         let default_value = entry.get();
                         let new_value = value.unwrap_or(1.0);
                         self.out_value = Some(default_value * new_value);
+                        self.digital_value = value.is_none();
                     }
                 }
                 ElementState::Released => {
@@ -213,9 +655,78 @@ impl InputAxisSet {
     }
 
     fn get_value(&self) -> Option<f32> {
+        if self.ramp.is_some() && self.digital_value {
+            if self.ramped_value == 0.0 {
+                return None;
+            }
+            return Some(self.ramped_value);
+        }
         self.out_value
     }
 
+    pub(crate) fn update_ramp(&mut self, delta_seconds: f32) {
+        let ramp = match self.ramp {
+            Some(ramp) => ramp,
+            None => return,
+        };
+        let target = self.out_value.unwrap_or(0.0);
+        if !self.digital_value {
+            self.ramped_value = target;
+            return;
+        }
+        let ramps_up = target != 0.0
+            && (self.ramped_value == 0.0 || self.ramped_value.signum() == target.signum())
+            && target.abs() > self.ramped_value.abs();
+        let speed = if ramps_up {
+            ramp.ramp_up
+        } else {
+            ramp.ramp_down
+        };
+        let difference = target - self.ramped_value;
+        let step = speed * delta_seconds;
+        if speed <= 0.0 || difference.abs() <= step {
+            self.ramped_value = target;
+        } else {
+            self.ramped_value += step * difference.signum();
+        }
+    }
+
+    pub(crate) fn apply_remote(&mut self, value: Option<f32>) {
+        match value {
+            Some(value) => {
+                if self.out_value.is_none() {
+                    self.state = InputState::ShouldBeActivated;
+                }
+                self.out_value = Some(value);
+                self.ramped_value = value;
+            }
+            None => {
+                if self.out_value.is_some() {
+                    self.state = InputState::ShouldBeDeactvated;
+                }
+                self.out_value = None;
+                self.ramped_value = 0.0;
+            }
+        }
+        self.digital_value = false;
+    }
+
+    pub(crate) fn accumulate_fixed(&mut self) {
+        let is_delta = self
+            .active_axis_types
+            .last()
+            .map_or(false, InputAxisType::is_delta);
+        if is_delta {
+            self.pending_delta += self.get_value().unwrap_or(0.0);
+        }
+    }
+
+    pub(crate) fn begin_fixed_tick(&mut self) {
+        self.fixed_delta = self.pending_delta;
+        self.pending_delta = 0.0;
+        self.fixed_value = self.get_value();
+    }
+
     pub(crate) fn update_state(&mut self) {
         match self.state {
             InputState::Released => {}
@@ -230,11 +741,26 @@ impl InputAxisSet {
         }
     }
 
+    pub(crate) fn release_all(&mut self) {
+        for axis_type in self.active_axis_types.clone() {
+            self.update_axis_state(axis_type, ElementState::Released, None);
+        }
+    }
+
+    pub(crate) fn release_inputs(&mut self, filter: &impl Fn(&InputAxisType) -> bool) {
+        for axis_type in self.active_axis_types.clone() {
+            if filter(&axis_type) {
+                self.update_axis_state(axis_type, ElementState::Released, None);
+            }
+        }
+    }
+
     pub(crate) fn reset_to_default(&mut self) {
         self.axises = self.default_axises.clone();
+        self.keys = self.default_keys.clone();
         self.active_axis_types = Vec::new();
-        self.state = InputState::Released;
         self.out_value = None;
+        self.ramped_value = 0.0;
     }
 
     pub(crate) fn apply_rebind(&mut self, rebind: &HashMap<InputAxisType, InputAxisType>) {
@@ -244,12 +770,16 @@ impl InputAxisSet {
                 self.axises.insert(val.clone(), axis_default_value);
             }
         }
+        for key in self.keys.iter_mut() {
+            if let Some(val) = rebind.get(key) {
+                *key = val.clone();
+            }
+        }
     }
 
-    pub(crate) fn get_axises(&self) -> Keys<InputAxisType, f32> {
-        self.default_axises.keys()
+    pub fn get_axises(&self) -> impl Iterator<Item = &InputAxisType> {
+        self.default_keys.iter()
     }
-    pub(crate) fn apply_new_defaults(&mut self, new_defaults: HashMap<InputAxisType, f32>) {}
 }
 #[derive(Clone)]
 pub struct UserInputSet<Key>
@@ -259,15 +789,19 @@ where
     name_to_keyset: HashMap<Key, InputKeyset>,
     name_to_axisset: HashMap<Key, InputAxisSet>,
     last_gamepad_axis_value: HashMap<GamepadAxisType, f32>,
+    name_to_metadata: HashMap<Key, ActionMetadata>,
+    binding_order: Vec<Key>,
 }
 
 pub struct AxisSetBuilder<'a, Key>
 where
     Key: PartialEq + Eq + Hash + Copy + Clone + Send + Sync,
 {
-    axises: HashSet<InputAxisType>,
+    axises: Vec<InputAxisType>,
     name: Key,
     owner_set: &'a mut UserInputSet<Key>,
+    ramp: Option<AxisRamp>,
+    metadata: Option<ActionMetadata>,
 }
 
 impl<'a, Key> AxisSetBuilder<'a, Key>
@@ -275,12 +809,28 @@ where
     Key: PartialEq + Eq + Hash + Copy + Clone + Send + Sync,
 {
     pub fn add(&mut self, axis_type: InputAxisType) -> &mut Self {
-        self.axises.insert(axis_type);
+        if !self.axises.contains(&axis_type) {
+            self.axises.push(axis_type);
+        }
+        self
+    }
+
+    pub fn ramp(&mut self, ramp_up: f32, ramp_down: f32) -> &mut Self {
+        self.ramp = Some(AxisRamp { ramp_up, ramp_down });
+        self
+    }
+
+    pub fn metadata(&mut self, metadata: ActionMetadata) -> &mut Self {
+        self.metadata = Some(metadata);
         self
     }
 
     fn finish(&mut self) {
-        self.owner_set.add_axisset(self.name, self.axises.clone());
+        self.owner_set
+            .add_axisset(self.name, self.axises.clone(), self.ramp);
+        if let Some(metadata) = self.metadata.take() {
+            self.owner_set.set_metadata(self.name, metadata);
+        }
     }
 }
 
@@ -298,9 +848,13 @@ where
     Key: PartialEq + Eq + Hash + Copy + Clone + Send + Sync,
 {
     axises: Vec<InputAxisType>,
+    axis_buttons: Vec<Option<AxisButton>>,
     name: Key,
     owner_set: &'a mut UserInputSet<Key>,
     repeat_all_for_reactivate: bool,
+    repeat: Option<KeyRepeat>,
+    buffer: Option<f32>,
+    metadata: Option<ActionMetadata>,
 }
 
 impl<'a, Key> KeySetBuilder<'a, Key>
@@ -309,21 +863,60 @@ where
 {
     pub fn add(&mut self, keys: &[InputAxisType]) -> &mut Self {
         let mut vec = keys.to_vec();
+        self.axis_buttons
+            .resize(self.axis_buttons.len() + vec.len(), None);
         self.axises.append(&mut vec);
         self
     }
 
+    pub fn add_axis(
+        &mut self,
+        axis: InputAxisType,
+        direction: AxisDirection,
+        press_threshold: f32,
+        release_threshold: f32,
+    ) -> &mut Self {
+        self.axises.push(axis);
+        self.axis_buttons.push(Some(AxisButton {
+            direction,
+            press_threshold,
+            release_threshold,
+        }));
+        self
+    }
+
     pub fn enable_repeat_all_for_reactivation(&mut self) -> &mut Self {
         self.repeat_all_for_reactivate = true;
         self
     }
 
+    pub fn repeat(&mut self, delay: f32, interval: f32) -> &mut Self {
+        self.repeat = Some(KeyRepeat { delay, interval });
+        self
+    }
+
+    pub fn buffer(&mut self, window: f32) -> &mut Self {
+        self.buffer = Some(window);
+        self
+    }
+
+    pub fn metadata(&mut self, metadata: ActionMetadata) -> &mut Self {
+        self.metadata = Some(metadata);
+        self
+    }
+
     fn finish(&mut self) {
         self.owner_set.add_keyset(
             self.name,
             self.axises.clone(),
+            self.axis_buttons.clone(),
             self.repeat_all_for_reactivate,
+            self.repeat,
+            self.buffer,
         );
+        if let Some(metadata) = self.metadata.take() {
+            self.owner_set.set_metadata(self.name, metadata);
+        }
     }
 }
 
@@ -345,45 +938,128 @@ where
             name_to_keyset: HashMap::default(),
             name_to_axisset: HashMap::default(),
             last_gamepad_axis_value: HashMap::default(),
+            name_to_metadata: HashMap::default(),
+            binding_order: Vec::new(),
         }
     }
 
     pub fn begin_key(&mut self, name: Key) -> KeySetBuilder<Key> {
         KeySetBuilder {
             axises: Vec::new(),
+            axis_buttons: Vec::new(),
             name: name,
             owner_set: self,
             repeat_all_for_reactivate: false,
+            repeat: None,
+            buffer: None,
+            metadata: None,
         }
     }
 
-    #[allow(dead_code)]
     pub(crate) fn add_keyset(
         &mut self,
         name: Key,
         keyset: Vec<InputAxisType>,
+        axis_buttons: Vec<Option<AxisButton>>,
         repeat_all_for_activate: bool,
+        repeat: Option<KeyRepeat>,
+        buffer: Option<f32>,
     ) {
-        self.name_to_keyset
-            .insert(name, InputKeyset::new(keyset, repeat_all_for_activate));
+        let mut keyset = InputKeyset::new(keyset, repeat_all_for_activate);
+        keyset.axis_buttons = axis_buttons;
+        keyset.repeat = repeat;
+        keyset.buffer_window = buffer;
+        self.name_to_keyset.insert(name, keyset);
+        self.add_binding_name(name);
     }
 
-    #[allow(dead_code)]
     pub fn begin_axis(&mut self, name: Key) -> AxisSetBuilder<Key> {
         AxisSetBuilder {
-            axises: HashSet::default(),
+            axises: Vec::new(),
             name: name,
             owner_set: self,
+            ramp: None,
+            metadata: None,
         }
     }
 
-    #[allow(dead_code)]
-    pub(crate) fn add_axisset(&mut self, name: Key, axises: HashSet<InputAxisType>) {
+    pub(crate) fn add_axisset(
+        &mut self,
+        name: Key,
+        axises: Vec<InputAxisType>,
+        ramp: Option<AxisRamp>,
+    ) {
         let mut map = HashMap::default();
         for axis in axises.iter() {
             map.insert(axis.clone(), 1.0);
         }
-        self.name_to_axisset.insert(name, InputAxisSet::new(map));
+        let mut axisset = InputAxisSet::new(map);
+        axisset.ramp = ramp;
+        axisset.default_keys = axises.clone();
+        axisset.keys = axises;
+        self.name_to_axisset.insert(name, axisset);
+        self.add_binding_name(name);
+    }
+
+    fn add_binding_name(&mut self, name: Key) {
+        if !self.binding_order.contains(&name) {
+            self.binding_order.push(name);
+        }
+        self.name_to_metadata.entry(name).or_default();
+    }
+
+    pub(crate) fn set_metadata(&mut self, name: Key, metadata: ActionMetadata) {
+        self.name_to_metadata.insert(name, metadata);
+    }
+
+    pub fn get_metadata(&self, name: Key) -> Option<&ActionMetadata> {
+        self.name_to_metadata.get(&name)
+    }
+
+    pub fn get_bindings(&self) -> Vec<BindingInfo<'_, Key>> {
+        self.binding_order
+            .iter()
+            .filter_map(|name| {
+                self.name_to_metadata.get(name).map(|metadata| BindingInfo {
+                    name: *name,
+                    metadata,
+                    keys: self.get_bound_inputs(*name),
+                })
+            })
+            .collect()
+    }
+
+    pub fn get_binding_view(&self, name: Key) -> Option<BindingView<'_, Key>> {
+        let keyset = self.name_to_keyset.get(&name);
+        let axisset = self.name_to_axisset.get(&name);
+        if keyset.is_none() && axisset.is_none() {
+            return None;
+        }
+        Some(BindingView {
+            name,
+            metadata: self.name_to_metadata.get(&name),
+            keyset,
+            axisset,
+        })
+    }
+
+    pub fn get_binding_views(&self) -> Vec<BindingView<'_, Key>> {
+        self.binding_order
+            .iter()
+            .filter_map(|name| self.get_binding_view(*name))
+            .collect()
+    }
+
+    pub fn get_categories(&self) -> Vec<&str> {
+        let mut categories: Vec<&str> = Vec::new();
+        for name in self.binding_order.iter() {
+            if let Some(metadata) = self.name_to_metadata.get(name) {
+                if !categories.contains(&metadata.category.as_str()) {
+                    categories.push(&metadata.category);
+                }
+            }
+        }
+        categories
     }
 
     pub fn get_axis_value(&self, name: Key) -> Option<f32> {
@@ -393,16 +1069,58 @@ where
         None
     }
 
-    #[allow(dead_code)]
     pub fn get_key_state(&self, name: Key) -> Option<ElementState> {
-        if let Some(val) = self.name_to_keyset.get(&name) {
-            if val.state == InputState::ShouldBeActivated {
-                return Some(ElementState::Pressed);
-            } else if val.state == InputState::ShouldBeDeactvated {
-                return Some(ElementState::Released);
-            }
+        self.name_to_keyset
+            .get(&name)
+            .and_then(|keyset| keyset.get_edge_state())
+    }
+
+    pub fn is_key_active(&self, name: Key) -> Option<bool> {
+        self.name_to_keyset
+            .get(&name)
+            .map(|keyset| keyset.is_active())
+    }
+
+    pub fn get_fixed_key_state(&self, name: Key) -> Option<ElementState> {
+        self.name_to_keyset
+            .get(&name)
+            .and_then(|keyset| keyset.fixed_state)
+    }
+
+    pub fn get_fixed_axis_value(&self, name: Key) -> Option<f32> {
+        self.name_to_axisset
+            .get(&name)
+            .and_then(|axisset| axisset.fixed_value)
+    }
+
+    pub fn get_fixed_axis_delta(&self, name: Key) -> Option<f32> {
+        self.name_to_axisset
+            .get(&name)
+            .map(|axisset| axisset.fixed_delta)
+    }
+
+    pub fn is_buffered(&self, name: Key) -> bool {
+        self.name_to_keyset
+            .get(&name)
+            .map_or(false, |keyset| keyset.buffered_time.is_some())
+    }
+
+    pub fn consume_buffered(&mut self, name: Key) -> bool {
+        self.name_to_keyset
+            .get_mut(&name)
+            .and_then(|keyset| keyset.buffered_time.take())
+            .is_some()
+    }
+
+    pub(crate) fn get_bound_inputs(&self, name: Key) -> Vec<InputAxisType> {
+        let mut inputs = Vec::new();
+        if let Some(keyset) = self.name_to_keyset.get(&name) {
+            inputs.extend(keyset.keys.iter().cloned());
         }
-        None
+        if let Some(axisset) = self.name_to_axisset.get(&name) {
+            inputs.extend(axisset.keys.iter().cloned());
+        }
+        inputs
     }
 
     pub(crate) fn change_key_state(&mut self, key_type: InputAxisType, state: ElementState) {
@@ -411,38 +1129,241 @@ where
         }
     }
 
-    pub(crate) fn change_axis_state(
+    // One physical key counts once per binding, by key code if bound, otherwise by scan code
+    pub(crate) fn change_physical_key_state(
         &mut self,
-        axis_type: InputAxisType,
+        inputs: &[InputAxisType],
         state: ElementState,
-        value: Option<f32>,
     ) {
-        for (_, keyset) in self.name_to_axisset.iter_mut() {
-            keyset.update_axis_state(axis_type.clone(), state, value);
-        }
-    }
-
-    pub(crate) fn update_states(&mut self) {
         for (_, keyset) in self.name_to_keyset.iter_mut() {
-            keyset.update_state();
+            if let Some(input) = inputs
+                .iter()
+                .find(|input| keyset.keys_state.contains_key(input))
+            {
+                keyset.update_key_state(input.clone(), state);
+            }
+        }
+        for (_, axisset) in self.name_to_axisset.iter_mut() {
+            if let Some(input) = inputs
+                .iter()
+                .find(|input| axisset.axises.contains_key(input))
+            {
+                axisset.update_axis_state(input.clone(), state, None);
+            }
+        }
+    }
+
+    pub(crate) fn change_key_value(
+        &mut self,
+        key_type: InputAxisType,
+        state: ElementState,
+        value: f32,
+    ) {
+        for (_, keyset) in self.name_to_keyset.iter_mut() {
+            keyset.update_key_value(key_type.clone(), state, value);
+        }
+    }
+
+    pub(crate) fn change_input_state(
+        &mut self,
+        input: InputAxisType,
+        state: ElementState,
+        value: Option<f32>,
+    ) {
+        let key_value = value.unwrap_or(match state {
+            ElementState::Pressed => 1.0,
+            ElementState::Released => 0.0,
+        });
+        self.change_key_value(input.clone(), state, key_value);
+        self.change_axis_state(input, state, value);
+    }
+
+    pub(crate) fn update_time(&mut self, delta_seconds: f32, fixed_ticks: bool) {
+        for (_, keyset) in self.name_to_keyset.iter_mut() {
+            keyset.update_repeat(delta_seconds);
+            keyset.update_toggle();
+            keyset.update_buffer(delta_seconds);
+            if fixed_ticks {
+                keyset.accumulate_fixed();
+            }
+        }
+        for (_, axisset) in self.name_to_axisset.iter_mut() {
+            axisset.update_ramp(delta_seconds);
+            if fixed_ticks {
+                axisset.accumulate_fixed();
+            }
+        }
+    }
+
+    pub(crate) fn begin_fixed_tick(&mut self) {
+        for (_, keyset) in self.name_to_keyset.iter_mut() {
+            keyset.begin_fixed_tick();
+        }
+        for (_, axisset) in self.name_to_axisset.iter_mut() {
+            axisset.begin_fixed_tick();
+        }
+    }
+
+    pub(crate) fn change_axis_state(
+        &mut self,
+        axis_type: InputAxisType,
+        state: ElementState,
+        value: Option<f32>,
+    ) {
+        for (_, keyset) in self.name_to_axisset.iter_mut() {
+            keyset.update_axis_state(axis_type.clone(), state, value);
+        }
+    }
+
+    pub(crate) fn release_all(&mut self) {
+        for (_, keyset) in self.name_to_keyset.iter_mut() {
+            keyset.release_all();
+        }
+        for (_, axisset) in self.name_to_axisset.iter_mut() {
+            axisset.release_all();
+        }
+        self.last_gamepad_axis_value.clear();
+    }
+
+    pub(crate) fn release_inputs(&mut self, filter: impl Fn(&InputAxisType) -> bool) {
+        for (_, keyset) in self.name_to_keyset.iter_mut() {
+            keyset.release_inputs(&filter);
+        }
+        for (_, axisset) in self.name_to_axisset.iter_mut() {
+            axisset.release_inputs(&filter);
+        }
+    }
+
+    pub(crate) fn update_states(&mut self) {
+        for (_, keyset) in self.name_to_keyset.iter_mut() {
+            keyset.update_state();
         }
         for (_, axisset) in self.name_to_axisset.iter_mut() {
             axisset.update_state();
         }
     }
     pub(crate) fn apply_config(&mut self, config: &InputConfig<Key>) {
-        for (_, keyset) in self.name_to_keyset.iter_mut() {
+        let metadata = &self.name_to_metadata;
+        let rebindable = |name: &Key| metadata.get(name).map_or(true, |meta| meta.rebindable);
+        for (name, keyset) in self.name_to_keyset.iter_mut() {
             keyset.reset_to_default();
-            keyset.apply_rebind(&config.convert_pressed_key_to);
+            if rebindable(name) {
+                keyset.apply_rebind(&config.convert_pressed_key_to);
+            }
         }
-        for (_, axisset) in self.name_to_axisset.iter_mut() {
+        for (name, axisset) in self.name_to_axisset.iter_mut() {
             axisset.reset_to_default();
-            axisset.apply_rebind(&config.convert_pressed_key_to);
+            if rebindable(name) {
+                axisset.apply_rebind(&config.convert_pressed_key_to);
+            }
 
             for (key, value) in axisset.axises.iter_mut() {
                 *value = config.get_default_value(key);
             }
         }
+        self.apply_toggles(config);
+    }
+
+    pub(crate) fn apply_toggles(&mut self, config: &InputConfig<Key>) {
+        for (name, keyset) in self.name_to_keyset.iter_mut() {
+            let toggle = config.is_toggle(name);
+            if keyset.toggle != toggle {
+                keyset.toggle = toggle;
+                keyset.toggled = false;
+            }
+        }
+    }
+
+    pub(crate) fn reset_toggles(&mut self) {
+        for (_, keyset) in self.name_to_keyset.iter_mut() {
+            keyset.toggled = false;
+        }
+    }
+}
+
+impl<Key> UserInputSet<Key>
+where
+    Key: PartialEq + Eq + Hash + Copy + Clone + Send + Sync + Ord,
+{
+    fn sorted_names<T>(map: &HashMap<Key, T>) -> Vec<Key> {
+        let mut names: Vec<Key> = map.keys().copied().collect();
+        names.sort();
+        names
+    }
+
+    pub fn snapshot(&self, frame: u32) -> InputSnapshot {
+        let mut snapshot = InputSnapshot {
+            frame,
+            ..Default::default()
+        };
+        for (index, name) in Self::sorted_names(&self.name_to_keyset).iter().enumerate() {
+            if self.name_to_keyset[name].is_held() {
+                set_bit(&mut snapshot.pressed, index);
+            }
+        }
+        for (index, name) in Self::sorted_names(&self.name_to_axisset).iter().enumerate() {
+            if let Some(value) = self.name_to_axisset[name].get_value() {
+                set_bit(&mut snapshot.active_axes, index);
+                snapshot.axis_values.push(quantize_axis(value));
+            }
+        }
+        snapshot
+    }
+
+    pub(crate) fn raw_state(&self) -> RawInputState {
+        RawInputState {
+            keys: Self::sorted_names(&self.name_to_keyset)
+                .iter()
+                .map(|name| {
+                    let keyset = &self.name_to_keyset[name];
+                    (keyset.keys_state.clone(), keyset.activated_keys_num)
+                })
+                .collect(),
+            axes: Self::sorted_names(&self.name_to_axisset)
+                .iter()
+                .map(|name| self.name_to_axisset[name].active_axis_types.clone())
+                .collect(),
+            source: None,
+        }
+    }
+
+    pub(crate) fn restore_raw_state(&mut self, raw: &RawInputState) {
+        for (name, (keys_state, activated_keys_num)) in Self::sorted_names(&self.name_to_keyset)
+            .iter()
+            .zip(raw.keys.iter())
+        {
+            if let Some(keyset) = self.name_to_keyset.get_mut(name) {
+                keyset.keys_state = keys_state.clone();
+                keyset.activated_keys_num = *activated_keys_num;
+            }
+        }
+        for (name, active_axis_types) in Self::sorted_names(&self.name_to_axisset)
+            .iter()
+            .zip(raw.axes.iter())
+        {
+            if let Some(axisset) = self.name_to_axisset.get_mut(name) {
+                axisset.active_axis_types = active_axis_types.clone();
+            }
+        }
+    }
+
+    pub(crate) fn apply_snapshot(&mut self, snapshot: &InputSnapshot) {
+        for (index, name) in Self::sorted_names(&self.name_to_keyset).iter().enumerate() {
+            if let Some(keyset) = self.name_to_keyset.get_mut(name) {
+                keyset.apply_remote(get_bit(&snapshot.pressed, index));
+            }
+        }
+        let mut axis_values = snapshot.axis_values.iter();
+        for (index, name) in Self::sorted_names(&self.name_to_axisset).iter().enumerate() {
+            let value = if get_bit(&snapshot.active_axes, index) {
+                axis_values.next().copied().map(dequantize_axis)
+            } else {
+                None
+            };
+            if let Some(axisset) = self.name_to_axisset.get_mut(name) {
+                axisset.apply_remote(value);
+            }
+        }
     }
 }
 
@@ -455,19 +1376,69 @@ where
     }
 }
 
-#[allow(dead_code)]
 pub enum TouchState {
     Pressed,
     Released,
     Moved,
 }
 
-#[allow(dead_code)]
-#[derive(Debug, Clone, Copy)]
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
 pub enum InputSource {
     Keyboard,
     Mouse,
     Gamepad,
+    Virtual,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct InputSourceChanged {
+    pub input_id: InputId,
+    pub source: InputSource,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct CursorRay {
+    pub origin: Vec3,
+    pub direction: Vec3,
+}
+
+const CURSOR_INPUTS: [InputAxisType; 6] = [
+    InputAxisType::MouseAxis(MouseAxisType::X),
+    InputAxisType::MouseAxis(MouseAxisType::Y),
+    InputAxisType::MouseAxisNormalized(MouseAxisType::X),
+    InputAxisType::MouseAxisNormalized(MouseAxisType::Y),
+    InputAxisType::MouseAxisCentered(MouseAxisType::X),
+    InputAxisType::MouseAxisCentered(MouseAxisType::Y),
+];
+const MOTION_INPUTS: [InputAxisType; 2] = [
+    InputAxisType::MouseAxisDiff(MouseAxisType::X),
+    InputAxisType::MouseAxisDiff(MouseAxisType::Y),
+];
+const WHEEL_INPUTS: [InputAxisType; 8] = [
+    InputAxisType::MouseAxis(MouseAxisType::Wheel),
+    InputAxisType::MouseAxis(MouseAxisType::HorizontalWheel),
+    InputAxisType::MouseAxisDiff(MouseAxisType::Wheel),
+    InputAxisType::MouseAxisDiff(MouseAxisType::HorizontalWheel),
+    InputAxisType::MouseWheel(MouseWheelDirection::Up),
+    InputAxisType::MouseWheel(MouseWheelDirection::Down),
+    InputAxisType::MouseWheel(MouseWheelDirection::Left),
+    InputAxisType::MouseWheel(MouseWheelDirection::Right),
+];
+
+#[derive(Debug, Clone)]
+pub(crate) enum InjectedInput {
+    KeyboardKey(KeyCode, ElementState),
+    KeyboardScanCode(u32, ElementState),
+    Character(char),
+    ReleaseAll,
+    MouseButton(MouseButton, ElementState),
+    CursorPosition(WindowId, Vec2),
+    MouseMotion(Vec2),
+    MouseWheel(Vec2, MouseScrollUnit),
+    Gamepad(Gamepad, GamepadEventType),
+    Player(u8, InputAxisType, ElementState, Option<f32>),
+    Snapshot(u8, InputSnapshot),
+    Virtual(Option<u8>, InputAxisType, ElementState, Option<f32>),
 }
 
 pub struct UserInputHandle<InputType, BindingType>
@@ -476,14 +1447,40 @@ where
     BindingType: PartialEq + Eq + Hash + Copy + Clone + Send + Sync,
 {
     mouse_position: Option<Vec2>,
+    mouse_window: Option<WindowId>,
+    window_sizes: HashMap<WindowId, Vec2>,
     mouse_delta: Option<Vec2>,
-    mouse_moved_this_tick: bool,
-    mouse_wheel_moved_this_tick: bool,
+    raw_mouse_delta: Option<Vec2>,
+    mouse_delta_history: VecDeque<Vec2>,
+    cursor_moved_windows: HashSet<WindowId>,
+    mouse_wheel_delta: Vec2,
+    mouse_wheel_notch_remainder: Vec2,
     input_id_to_inputset: HashMap<u8, UserInputSet<BindingType>>,
     input_id_to_input_type: HashMap<u8, InputType>,
+    input_id_to_window: HashMap<u8, WindowId>,
     available_sets: HashMap<InputType, UserInputSet<BindingType>>,
     last_input_source: Option<InputSource>,
+    input_id_to_input_source: HashMap<u8, InputSource>,
+    input_source_changes: Vec<InputSourceChanged>,
+    mouse_source_threshold: f32,
+    gamepad_axis_source_threshold: f32,
     config: InputConfig<BindingType>,
+    injected_inputs: Vec<InjectedInput>,
+    text_input_for_all: bool,
+    text_input_ids: HashSet<u8>,
+    text_input_allowed_keys: Vec<InputAxisType>,
+    received_text: String,
+    release_on_focus_loss: bool,
+    rumble: RumbleMixer,
+    rumble_backend: Option<Box<dyn RumbleBackend>>,
+    device_claims: DeviceClaims,
+    connected_gamepads: HashSet<Gamepad>,
+    fixed_ticks: bool,
+    input_processed: bool,
+    input_paused: bool,
+    blocked_devices: HashSet<InputDevice>,
+    consumed_mouse_buttons: HashSet<MouseButton>,
+    rollback: InputHistory,
 }
 
 impl<InputType, BindingType> Default for UserInputHandle<InputType, BindingType>
@@ -504,35 +1501,228 @@ where
     pub fn new() -> Self {
         Self {
             mouse_position: None,
+            mouse_window: None,
+            window_sizes: HashMap::default(),
             mouse_delta: None,
-            mouse_moved_this_tick: false,
-            mouse_wheel_moved_this_tick: false,
+            raw_mouse_delta: None,
+            mouse_delta_history: VecDeque::new(),
+            cursor_moved_windows: HashSet::default(),
+            mouse_wheel_delta: Vec2::ZERO,
+            mouse_wheel_notch_remainder: Vec2::ZERO,
             input_id_to_inputset: HashMap::default(),
             input_id_to_input_type: HashMap::default(),
+            input_id_to_window: HashMap::default(),
             available_sets: HashMap::default(),
             last_input_source: None,
+            input_id_to_input_source: HashMap::default(),
+            input_source_changes: Vec::new(),
+            mouse_source_threshold: 2.0,
+            gamepad_axis_source_threshold: 0.3,
             config: InputConfig::new(),
+            injected_inputs: Vec::new(),
+            text_input_for_all: false,
+            text_input_ids: HashSet::default(),
+            text_input_allowed_keys: vec![
+                InputAxisType::KeyboardButton(KeyCode::Escape),
+                InputAxisType::KeyboardButton(KeyCode::Return),
+                InputAxisType::KeyboardButton(KeyCode::NumpadEnter),
+            ],
+            received_text: String::new(),
+            release_on_focus_loss: true,
+            rumble: RumbleMixer::default(),
+            rumble_backend: None,
+            device_claims: DeviceClaims::default(),
+            connected_gamepads: HashSet::default(),
+            fixed_ticks: false,
+            input_processed: false,
+            input_paused: true,
+            blocked_devices: HashSet::default(),
+            consumed_mouse_buttons: HashSet::default(),
+            rollback: InputHistory::default(),
+        }
+    }
+    pub(crate) fn process_keyboard_event(
+        &mut self,
+        key_code: Option<KeyCode>,
+        scan_code: Option<u32>,
+        new_state: ElementState,
+    ) {
+        let inputs: Vec<InputAxisType> = key_code
+            .map(InputAxisType::KeyboardButton)
+            .into_iter()
+            .chain(scan_code.map(InputAxisType::KeyboardScanCode))
+            .collect();
+        // Releases pass, so keys held before text input don't stick
+        let blocked_by_text_input = new_state == ElementState::Pressed
+            && !inputs
+                .iter()
+                .any(|input| self.text_input_allowed_keys.contains(input));
+        for (player_id, player_set) in self.input_id_to_inputset.iter_mut() {
+            if blocked_by_text_input
+                && (self.text_input_for_all || self.text_input_ids.contains(player_id))
+            {
+                continue;
+            }
+            if !self
+                .device_claims
+                .accepts_event(*player_id, InputDevice::Keyboard, &inputs)
+            {
+                continue;
+            }
+            player_set.change_physical_key_state(&inputs, new_state);
+        }
+        self.track_input_source(None, InputSource::Keyboard, &inputs);
+    }
+    pub(crate) fn begin_frame(&mut self) {
+        self.finish_processing();
+        if self.input_processed {
+            self.input_paused = false;
+        } else if !self.input_paused {
+            self.process_release_all();
+            self.input_paused = true;
+        }
+        self.input_processed = false;
+    }
+    pub(crate) fn mark_processed(&mut self) {
+        self.input_processed = true;
+    }
+    pub(crate) fn set_blocked_devices(&mut self, blocked: HashSet<InputDevice>) {
+        for device in blocked.iter() {
+            if self.blocked_devices.contains(device) {
+                continue;
+            }
+            for (player_id, player_set) in self.input_id_to_inputset.iter_mut() {
+                if self.device_claims.accepts(*player_id, *device, None) {
+                    player_set.release_inputs(|input| device.produces(input));
+                }
+            }
         }
+        self.blocked_devices = blocked;
     }
-    pub(crate) fn process_keyboard_key(&mut self, key: KeyCode, new_state: ElementState) {
+    pub(crate) fn update_time(&mut self, delta_seconds: f32) {
         for (_, player_set) in self.input_id_to_inputset.iter_mut() {
-            player_set.change_key_state(InputAxisType::KeyboardButton(key), new_state);
-            player_set.change_axis_state(InputAxisType::KeyboardButton(key), new_state, None);
+            player_set.update_time(delta_seconds, self.fixed_ticks);
         }
-        self.last_input_source = Some(InputSource::Keyboard);
     }
-    pub(crate) fn process_mouse(&mut self, current_position: Vec2, delta_position: Vec2) {
+    pub(crate) fn begin_fixed_tick(&mut self) {
+        self.fixed_ticks = true;
         for (_, player_set) in self.input_id_to_inputset.iter_mut() {
-            player_set.change_axis_state(
-                InputAxisType::MouseAxis(MouseAxisType::X),
-                ElementState::Pressed,
-                Some(current_position.x),
-            );
-            player_set.change_axis_state(
-                InputAxisType::MouseAxis(MouseAxisType::Y),
-                ElementState::Pressed,
-                Some(current_position.y),
-            );
+            player_set.begin_fixed_tick();
+        }
+    }
+    pub(crate) fn process_release_all(&mut self) {
+        for (_, player_set) in self.input_id_to_inputset.iter_mut() {
+            player_set.release_all();
+        }
+        self.raw_mouse_delta = None;
+        self.consumed_mouse_buttons.clear();
+        self.mouse_delta_history.clear();
+        self.mouse_wheel_notch_remainder = Vec2::ZERO;
+    }
+    pub(crate) fn process_window_focus(&mut self, focused: bool) {
+        if !focused && self.release_on_focus_loss {
+            self.process_release_all();
+        }
+    }
+    pub(crate) fn process_received_character(&mut self, character: char) {
+        // Backspace, Enter and Tab also arrive as characters, they are handled as keys
+        if character.is_control() {
+            return;
+        }
+        if self.text_input_for_all || !self.text_input_ids.is_empty() {
+            self.received_text.push(character);
+        }
+    }
+    pub(crate) fn process_mouse_position(&mut self, window: WindowId, current_position: Vec2) {
+        let normalized_position = self
+            .window_sizes
+            .get(&window)
+            .map(|window_size| current_position / *window_size);
+        let positions: [(fn(MouseAxisType) -> InputAxisType, Option<Vec2>); 3] = [
+            (InputAxisType::MouseAxis, Some(current_position)),
+            (InputAxisType::MouseAxisNormalized, normalized_position),
+            (
+                InputAxisType::MouseAxisCentered,
+                normalized_position.map(|position| position * 2.0 - Vec2::ONE),
+            ),
+        ];
+        for (player_id, player_set) in self.input_id_to_inputset.iter_mut() {
+            if let Some(player_window) = self.input_id_to_window.get(player_id) {
+                if *player_window != window {
+                    continue;
+                }
+            }
+            if !self
+                .device_claims
+                .accepts(*player_id, InputDevice::Mouse, None)
+            {
+                continue;
+            }
+            for &(axis_type, position) in positions.iter() {
+                let position = match position {
+                    Some(position) => position,
+                    None => continue,
+                };
+                player_set.change_axis_state(
+                    axis_type(MouseAxisType::X),
+                    ElementState::Pressed,
+                    Some(position.x),
+                );
+                player_set.change_axis_state(
+                    axis_type(MouseAxisType::Y),
+                    ElementState::Pressed,
+                    Some(position.y),
+                );
+            }
+        }
+
+        let moved_distance = self
+            .mouse_position
+            .map_or(f32::MAX, |position| position.distance(current_position));
+        if moved_distance >= self.mouse_source_threshold {
+            self.track_input_source(None, InputSource::Mouse, &CURSOR_INPUTS);
+        }
+
+        self.mouse_position = Some(current_position);
+        self.mouse_window = Some(window);
+
+        self.cursor_moved_windows.insert(window);
+    }
+    pub(crate) fn process_mouse_motion(&mut self, delta_position: Vec2) {
+        self.raw_mouse_delta = Some(self.raw_mouse_delta.unwrap_or(Vec2::ZERO) + delta_position);
+    }
+    pub(crate) fn flush_mouse_motion(&mut self) {
+        let raw_delta = self.raw_mouse_delta.take();
+        if raw_delta.map_or(false, |delta| delta.length() >= self.mouse_source_threshold) {
+            self.track_input_source(None, InputSource::Mouse, &MOTION_INPUTS);
+        }
+        let settings = self.config.get_mouse_settings();
+        if raw_delta.is_none() && self.mouse_delta_history.is_empty() {
+            return;
+        }
+
+        self.mouse_delta_history
+            .push_back(settings.apply(raw_delta.unwrap_or(Vec2::ZERO)));
+        while self.mouse_delta_history.len() > settings.smoothing_frames.max(1) {
+            self.mouse_delta_history.pop_front();
+        }
+        let delta_position = self
+            .mouse_delta_history
+            .iter()
+            .fold(Vec2::ZERO, |sum, delta| sum + *delta)
+            / self.mouse_delta_history.len() as f32;
+        if raw_delta.is_none() && delta_position == Vec2::ZERO {
+            self.mouse_delta_history.clear();
+            return;
+        }
+
+        for (player_id, player_set) in self.input_id_to_inputset.iter_mut() {
+            if !self
+                .device_claims
+                .accepts(*player_id, InputDevice::Mouse, None)
+            {
+                continue;
+            }
             player_set.change_axis_state(
                 InputAxisType::MouseAxisDiff(MouseAxisType::X),
                 ElementState::Pressed,
@@ -545,42 +1735,99 @@ where
             );
         }
 
-        self.mouse_position = Some(current_position);
         self.mouse_delta = Some(delta_position);
-
-        self.mouse_moved_this_tick = true;
-        self.last_input_source = Some(InputSource::Mouse);
     }
     pub(crate) fn process_mouse_button(&mut self, button: MouseButton, new_state: ElementState) {
-        for (_, player_set) in self.input_id_to_inputset.iter_mut() {
+        if self.consumed_mouse_buttons.contains(&button) {
+            if new_state == ElementState::Released {
+                self.consumed_mouse_buttons.remove(&button);
+            }
+            return;
+        }
+        let input = InputAxisType::MouseButton(button);
+        for (player_id, player_set) in self.input_id_to_inputset.iter_mut() {
+            if !self
+                .device_claims
+                .accepts(*player_id, InputDevice::Mouse, Some(&input))
+            {
+                continue;
+            }
             player_set.change_key_state(InputAxisType::MouseButton(button), new_state);
             player_set.change_axis_state(InputAxisType::MouseButton(button), new_state, None);
         }
-        self.last_input_source = Some(InputSource::Mouse);
+        self.track_input_source(None, InputSource::Mouse, &[input]);
     }
-    pub(crate) fn process_mouse_wheel(&mut self, delta: Vec2) {
-        for (_, player_set) in self.input_id_to_inputset.iter_mut() {
-            player_set.change_key_state(
-                InputAxisType::MouseAxis(MouseAxisType::Wheel),
-                ElementState::Pressed,
-            );
-            player_set.change_key_state(
-                InputAxisType::MouseAxisDiff(MouseAxisType::Wheel),
-                ElementState::Pressed,
-            );
-            player_set.change_axis_state(
-                InputAxisType::MouseAxis(MouseAxisType::Wheel),
-                ElementState::Pressed,
-                Some(delta.y),
-            );
-            player_set.change_axis_state(
-                InputAxisType::MouseAxisDiff(MouseAxisType::Wheel),
-                ElementState::Pressed,
-                Some(delta.y),
-            );
+    pub(crate) fn process_mouse_wheel(&mut self, delta: Vec2, unit: MouseScrollUnit) {
+        let delta = match unit {
+            MouseScrollUnit::Line => delta,
+            MouseScrollUnit::Pixel => self.config.get_mouse_settings().pixels_to_lines(delta),
+        };
+        self.mouse_wheel_delta += delta;
+        self.mouse_wheel_notch_remainder += delta;
+
+        let mut notches = Vec::new();
+        let remainder = &mut self.mouse_wheel_notch_remainder;
+        for (value, positive, negative) in [
+            (
+                &mut remainder.y,
+                MouseWheelDirection::Up,
+                MouseWheelDirection::Down,
+            ),
+            (
+                &mut remainder.x,
+                MouseWheelDirection::Right,
+                MouseWheelDirection::Left,
+            ),
+        ] {
+            let whole_notches = value.trunc();
+            *value -= whole_notches;
+            if whole_notches > 0.0 {
+                notches.push(positive);
+            } else if whole_notches < 0.0 {
+                notches.push(negative);
+            }
+        }
+
+        let wheel_delta = self.mouse_wheel_delta;
+        for (player_id, player_set) in self.input_id_to_inputset.iter_mut() {
+            if !self
+                .device_claims
+                .accepts(*player_id, InputDevice::Mouse, None)
+            {
+                continue;
+            }
+            for (axis, value) in [
+                (MouseAxisType::Wheel, wheel_delta.y),
+                (MouseAxisType::HorizontalWheel, wheel_delta.x),
+            ] {
+                if value == 0.0 {
+                    continue;
+                }
+                player_set.change_key_state(InputAxisType::MouseAxis(axis), ElementState::Pressed);
+                player_set
+                    .change_key_state(InputAxisType::MouseAxisDiff(axis), ElementState::Pressed);
+                player_set.change_axis_state(
+                    InputAxisType::MouseAxis(axis),
+                    ElementState::Pressed,
+                    Some(value),
+                );
+                player_set.change_axis_state(
+                    InputAxisType::MouseAxisDiff(axis),
+                    ElementState::Pressed,
+                    Some(value),
+                );
+            }
+            for direction in notches.iter() {
+                player_set
+                    .change_key_state(InputAxisType::MouseWheel(*direction), ElementState::Pressed);
+                player_set.change_axis_state(
+                    InputAxisType::MouseWheel(*direction),
+                    ElementState::Pressed,
+                    None,
+                );
+            }
         }
-        self.mouse_wheel_moved_this_tick = true;
-        self.last_input_source = Some(InputSource::Mouse);
+        self.track_input_source(None, InputSource::Mouse, &WHEEL_INPUTS);
     }
 
     //TODO: rewrite
@@ -665,30 +1912,49 @@ where
     //     }
     // }
 
-    #[allow(dead_code)]
     pub(crate) fn process_gamepad(&mut self, gamepad: Gamepad, event: GamepadEventType) {
         //TODO: Write connection logic
         match event {
-            GamepadEventType::Connected => {}
-            GamepadEventType::Disconnected => {}
+            // Claims are kept, so a reconnected gamepad returns to its player
+            GamepadEventType::Connected => {
+                self.connected_gamepads.insert(gamepad);
+            }
+            GamepadEventType::Disconnected => {
+                self.connected_gamepads.remove(&gamepad);
+                self.rumble.stop(gamepad);
+            }
             GamepadEventType::ButtonChanged(btn_type, value) => {
                 let state = if value.abs() <= 0.1 {
                     ElementState::Released
                 } else {
                     ElementState::Pressed
                 };
+                let input = InputAxisType::GamepadButton(btn_type);
+                let mut receivers = Vec::new();
                 for (player_id, player_set) in self.input_id_to_inputset.iter_mut() {
-                    if *player_id == gamepad.0 as u8 {
-                        player_set.change_key_state(InputAxisType::GamepadButton(btn_type), state);
+                    if self.device_claims.accepts(
+                        *player_id,
+                        InputDevice::Gamepad(gamepad),
+                        Some(&input),
+                    ) {
+                        receivers.push(*player_id);
+                        player_set.change_key_value(
+                            InputAxisType::GamepadButton(btn_type),
+                            state,
+                            value,
+                        );
                         player_set.change_axis_state(
                             InputAxisType::GamepadButton(btn_type),
                             state,
                             Some(value),
                         );
-                        break;
                     }
                 }
-                self.last_input_source = Some(InputSource::Gamepad);
+                if state == ElementState::Pressed {
+                    for player_id in receivers {
+                        self.track_input_source(Some(player_id), InputSource::Gamepad, &[]);
+                    }
+                }
             }
             GamepadEventType::AxisChanged(axis_type, value) => {
                 let state = if value.abs() <= 0.1 {
@@ -696,9 +1962,20 @@ where
                 } else {
                     ElementState::Pressed
                 };
+                let input = InputAxisType::GamepadAxis(axis_type);
+                let mut receivers = Vec::new();
                 for (player_id, player_set) in self.input_id_to_inputset.iter_mut() {
-                    if *player_id == gamepad.0 as u8 {
-                        player_set.change_key_state(InputAxisType::GamepadAxis(axis_type), state);
+                    if self.device_claims.accepts(
+                        *player_id,
+                        InputDevice::Gamepad(gamepad),
+                        Some(&input),
+                    ) {
+                        receivers.push(*player_id);
+                        player_set.change_key_value(
+                            InputAxisType::GamepadAxis(axis_type),
+                            state,
+                            value,
+                        );
                         player_set.change_axis_state(
                             InputAxisType::GamepadAxis(axis_type),
                             state,
@@ -719,94 +1996,368 @@ where
                             state,
                             Some(diff),
                         );
-                        break;
                     }
                 }
 
-                self.last_input_source = Some(InputSource::Gamepad);
+                if value.abs() >= self.gamepad_axis_source_threshold {
+                    for player_id in receivers {
+                        self.track_input_source(Some(player_id), InputSource::Gamepad, &[]);
+                    }
+                }
             }
         }
     }
 
-    #[allow(dead_code)]
-    pub fn switch_input(&mut self, component: &'_ InputId, input_type: InputType) {
-        let should_change_input =
-            if let Some(exists_input) = self.input_id_to_input_type.get(&component.id) {
-                *exists_input == input_type
-            } else {
-                true
-            };
-        if should_change_input {
-            if let Entry::Occupied(set) = self.available_sets.entry(input_type) {
-                self.input_id_to_inputset
-                    .insert(component.id, set.get().clone());
-                self.input_id_to_input_type.insert(component.id, input_type);
+    pub(crate) fn process_virtual_input(
+        &mut self,
+        input_id: Option<u8>,
+        input: InputAxisType,
+        state: ElementState,
+        value: Option<f32>,
+    ) {
+        for (player_id, player_set) in self.input_id_to_inputset.iter_mut() {
+            if input_id.map_or(false, |input_id| input_id != *player_id)
+                || self.device_claims.is_remote(*player_id)
+            {
+                continue;
             }
+            player_set.change_input_state(input.clone(), state, value);
+        }
+        if state == ElementState::Pressed {
+            self.track_input_source(input_id, InputSource::Virtual, &[]);
         }
     }
 
-    #[allow(dead_code)]
-    pub fn add_input(&mut self, input_type: InputType, input_set: UserInputSet<BindingType>) {
-        let map = self.available_sets.insert_or_get(input_type);
-        *map = input_set;
+    pub fn press_key(&mut self, key: KeyCode) {
+        self.injected_inputs
+            .push(InjectedInput::KeyboardKey(key, ElementState::Pressed));
     }
 
-    fn update_states(&mut self) {
-        for (_, player_set) in self.input_id_to_inputset.iter_mut() {
-            player_set.update_states();
-        }
+    pub fn release_key(&mut self, key: KeyCode) {
+        self.injected_inputs
+            .push(InjectedInput::KeyboardKey(key, ElementState::Released));
     }
 
-    pub(crate) fn finish_processing(&mut self) {
-        self.update_states();
-        for (_, player_set) in self.input_id_to_inputset.iter_mut() {
-            if !self.mouse_moved_this_tick {
-                player_set.change_axis_state(
-                    InputAxisType::MouseAxis(MouseAxisType::X),
-                    ElementState::Released,
-                    None,
-                );
-                player_set.change_axis_state(
-                    InputAxisType::MouseAxis(MouseAxisType::Y),
-                    ElementState::Released,
-                    None,
-                );
-                player_set.change_axis_state(
-                    InputAxisType::MouseAxisDiff(MouseAxisType::X),
-                    ElementState::Released,
-                    None,
-                );
-                player_set.change_axis_state(
-                    InputAxisType::MouseAxisDiff(MouseAxisType::Y),
-                    ElementState::Released,
-                    None,
-                );
-            };
-            if !self.mouse_wheel_moved_this_tick {
-                player_set.change_axis_state(
-                    InputAxisType::MouseAxis(MouseAxisType::Wheel),
-                    ElementState::Released,
-                    None,
-                );
+    pub fn press_scan_code(&mut self, scan_code: u32) {
+        self.injected_inputs.push(InjectedInput::KeyboardScanCode(
+            scan_code,
+            ElementState::Pressed,
+        ));
+    }
+
+    pub fn release_scan_code(&mut self, scan_code: u32) {
+        self.injected_inputs.push(InjectedInput::KeyboardScanCode(
+            scan_code,
+            ElementState::Released,
+        ));
+    }
+
+    pub fn type_character(&mut self, character: char) {
+        self.injected_inputs
+            .push(InjectedInput::Character(character));
+    }
+
+    pub fn release_all_inputs(&mut self) {
+        self.injected_inputs.push(InjectedInput::ReleaseAll);
+    }
+
+    pub fn press_mouse_button(&mut self, button: MouseButton) {
+        self.injected_inputs
+            .push(InjectedInput::MouseButton(button, ElementState::Pressed));
+    }
+
+    pub fn release_mouse_button(&mut self, button: MouseButton) {
+        self.injected_inputs
+            .push(InjectedInput::MouseButton(button, ElementState::Released));
+    }
+
+    pub fn consume_mouse_button(&mut self, button: MouseButton) {
+        self.consumed_mouse_buttons.insert(button);
+    }
+
+    pub fn move_cursor(&mut self, position: Vec2) {
+        self.move_cursor_in_window(WindowId::primary(), position);
+    }
+
+    pub fn move_cursor_in_window(&mut self, window: WindowId, position: Vec2) {
+        self.injected_inputs
+            .push(InjectedInput::CursorPosition(window, position));
+    }
+
+    pub fn set_window_size(&mut self, window: WindowId, size: Vec2) {
+        self.window_sizes.insert(window, size);
+    }
+
+    // Sizes of closed windows are dropped, bevy has no event for a closed window
+    pub(crate) fn retain_window_sizes(&mut self, is_open: impl Fn(WindowId) -> bool) {
+        self.window_sizes.retain(|window, _| is_open(*window));
+    }
+
+    pub fn move_mouse(&mut self, delta: Vec2) {
+        self.injected_inputs.push(InjectedInput::MouseMotion(delta));
+    }
+
+    pub fn scroll_mouse_wheel(&mut self, delta: Vec2, unit: MouseScrollUnit) {
+        self.injected_inputs
+            .push(InjectedInput::MouseWheel(delta, unit));
+    }
+
+    pub fn set_gamepad_button(&mut self, gamepad: Gamepad, button: GamepadButtonType, value: f32) {
+        self.injected_inputs.push(InjectedInput::Gamepad(
+            gamepad,
+            GamepadEventType::ButtonChanged(button, value),
+        ));
+    }
+
+    pub fn set_gamepad_axis(&mut self, gamepad: Gamepad, axis: GamepadAxisType, value: f32) {
+        self.injected_inputs.push(InjectedInput::Gamepad(
+            gamepad,
+            GamepadEventType::AxisChanged(axis, value),
+        ));
+    }
+
+    pub fn set_virtual_button(&mut self, component: Option<&InputId>, button: u8, pressed: bool) {
+        let state = if pressed {
+            ElementState::Pressed
+        } else {
+            ElementState::Released
+        };
+        self.injected_inputs.push(InjectedInput::Virtual(
+            component.map(|component| component.id),
+            InputAxisType::VirtualButton(button),
+            state,
+            None,
+        ));
+    }
+
+    pub fn set_virtual_stick(&mut self, component: Option<&InputId>, stick: u8, value: Vec2) {
+        for (axis, axis_value) in [
+            (VirtualStickAxis::X, value.x),
+            (VirtualStickAxis::Y, value.y),
+        ] {
+            let state = if axis_value == 0.0 {
+                ElementState::Released
+            } else {
+                ElementState::Pressed
+            };
+            self.injected_inputs.push(InjectedInput::Virtual(
+                component.map(|component| component.id),
+                InputAxisType::VirtualStick(stick, axis),
+                state,
+                Some(axis_value),
+            ));
+        }
+    }
+
+    pub fn inject_input(
+        &mut self,
+        component: &'_ InputId,
+        input: InputAxisType,
+        state: ElementState,
+        value: Option<f32>,
+    ) {
+        self.injected_inputs
+            .push(InjectedInput::Player(component.id, input, state, value));
+    }
+
+    fn track_input_source(
+        &mut self,
+        input_id: Option<u8>,
+        source: InputSource,
+        inputs: &[InputAxisType],
+    ) {
+        self.last_input_source = Some(source);
+        let input_ids: Vec<u8> = match input_id {
+            Some(input_id) if self.input_id_to_inputset.contains_key(&input_id) => vec![input_id],
+            Some(_) => Vec::new(),
+            None => {
+                let device = match source {
+                    InputSource::Mouse => Some(InputDevice::Mouse),
+                    InputSource::Keyboard => Some(InputDevice::Keyboard),
+                    InputSource::Gamepad | InputSource::Virtual => None,
+                };
+                self.input_id_to_inputset
+                    .keys()
+                    .copied()
+                    .filter(|input_id| match device {
+                        Some(device) => self.device_claims.is_source(*input_id, device, inputs),
+                        None => !self.device_claims.is_remote(*input_id),
+                    })
+                    .collect()
+            }
+        };
+        for input_id in input_ids {
+            if self.input_id_to_input_source.get(&input_id) != Some(&source) {
+                self.input_id_to_input_source.insert(input_id, source);
+                self.input_source_changes.push(InputSourceChanged {
+                    input_id: InputId::new(input_id),
+                    source,
+                });
+            }
+        }
+    }
+
+    pub(crate) fn take_input_source_changes(&mut self) -> Vec<InputSourceChanged> {
+        std::mem::take(&mut self.input_source_changes)
+    }
+
+    pub fn set_input_source_thresholds(&mut self, mouse_distance: f32, gamepad_axis: f32) {
+        self.mouse_source_threshold = mouse_distance;
+        self.gamepad_axis_source_threshold = gamepad_axis;
+    }
+
+    pub(crate) fn process_injected_inputs(&mut self) {
+        let injected_inputs = std::mem::take(&mut self.injected_inputs);
+        for input in injected_inputs {
+            match input {
+                InjectedInput::KeyboardKey(key, state) => {
+                    self.process_keyboard_event(Some(key), None, state)
+                }
+                InjectedInput::KeyboardScanCode(scan_code, state) => {
+                    self.process_keyboard_event(None, Some(scan_code), state)
+                }
+                InjectedInput::Character(character) => self.process_received_character(character),
+                InjectedInput::ReleaseAll => self.process_release_all(),
+                InjectedInput::MouseButton(button, state) => {
+                    self.process_mouse_button(button, state)
+                }
+                InjectedInput::CursorPosition(window, position) => {
+                    self.process_mouse_position(window, position)
+                }
+                InjectedInput::MouseMotion(delta) => self.process_mouse_motion(delta),
+                InjectedInput::MouseWheel(delta, unit) => self.process_mouse_wheel(delta, unit),
+                InjectedInput::Gamepad(gamepad, event) => self.process_gamepad(gamepad, event),
+                InjectedInput::Player(id, input, state, value) => {
+                    if let Some(player_set) = self.input_id_to_inputset.get_mut(&id) {
+                        player_set.change_input_state(input, state, value);
+                    }
+                }
+                InjectedInput::Snapshot(id, snapshot) => {
+                    if let Some(player_set) = self.input_id_to_inputset.get_mut(&id) {
+                        player_set.apply_snapshot(&snapshot);
+                    }
+                }
+                InjectedInput::Virtual(id, input, state, value) => {
+                    self.process_virtual_input(id, input, state, value)
+                }
+            }
+        }
+    }
+
+    pub fn switch_input(&mut self, component: &'_ InputId, input_type: InputType) {
+        // Toggles latch per input context, so every switch turns them off
+        if let Some(player_set) = self.input_id_to_inputset.get_mut(&component.id) {
+            player_set.reset_toggles();
+        }
+        let should_change_input =
+            if let Some(exists_input) = self.input_id_to_input_type.get(&component.id) {
+                *exists_input == input_type
+            } else {
+                true
+            };
+        if should_change_input {
+            if let Entry::Occupied(set) = self.available_sets.entry(input_type) {
+                self.input_id_to_inputset
+                    .insert(component.id, set.get().clone());
+                self.input_id_to_input_type.insert(component.id, input_type);
+            }
+        }
+    }
+
+    pub fn add_input(&mut self, input_type: InputType, input_set: UserInputSet<BindingType>) {
+        let map = self.available_sets.insert_or_get(input_type);
+        *map = input_set;
+    }
+
+    pub fn get_input_ids(&self) -> Vec<InputId> {
+        let mut ids: Vec<u8> = self.input_id_to_inputset.keys().copied().collect();
+        ids.sort_unstable();
+        ids.into_iter().map(InputId::new).collect()
+    }
+
+    pub fn get_player_set(&self, component: &'_ InputId) -> Option<&UserInputSet<BindingType>> {
+        self.input_id_to_inputset.get(&component.id)
+    }
+
+    pub fn get_player_input_type(&self, component: &'_ InputId) -> Option<&InputType> {
+        self.input_id_to_input_type.get(&component.id)
+    }
+
+    pub fn available_sets(&self) -> impl Iterator<Item = (&InputType, &UserInputSet<BindingType>)> {
+        self.available_sets.iter()
+    }
+
+    fn update_states(&mut self) {
+        for (_, player_set) in self.input_id_to_inputset.iter_mut() {
+            player_set.update_states();
+        }
+    }
+
+    pub(crate) fn finish_processing(&mut self) {
+        self.update_states();
+        let moved_windows = &self.cursor_moved_windows;
+        for (player_id, player_set) in self.input_id_to_inputset.iter_mut() {
+            let cursor_moved = match self.input_id_to_window.get(player_id) {
+                Some(window) => moved_windows.contains(window),
+                None => !moved_windows.is_empty(),
+            };
+            if !cursor_moved {
+                for axis in [MouseAxisType::X, MouseAxisType::Y] {
+                    for axis_type in [
+                        InputAxisType::MouseAxis(axis),
+                        InputAxisType::MouseAxisNormalized(axis),
+                        InputAxisType::MouseAxisCentered(axis),
+                    ] {
+                        player_set.change_axis_state(axis_type, ElementState::Released, None);
+                    }
+                }
+            };
+            player_set.change_axis_state(
+                InputAxisType::MouseAxisDiff(MouseAxisType::X),
+                ElementState::Released,
+                None,
+            );
+            player_set.change_axis_state(
+                InputAxisType::MouseAxisDiff(MouseAxisType::Y),
+                ElementState::Released,
+                None,
+            );
+            for axis in [MouseAxisType::Wheel, MouseAxisType::HorizontalWheel] {
                 player_set.change_axis_state(
-                    InputAxisType::MouseAxisDiff(MouseAxisType::Wheel),
+                    InputAxisType::MouseAxis(axis),
                     ElementState::Released,
                     None,
                 );
-                player_set.change_key_state(
-                    InputAxisType::MouseAxis(MouseAxisType::Wheel),
+                player_set.change_axis_state(
+                    InputAxisType::MouseAxisDiff(axis),
                     ElementState::Released,
+                    None,
                 );
-                player_set.change_key_state(
-                    InputAxisType::MouseAxisDiff(MouseAxisType::Wheel),
+                player_set.change_key_state(InputAxisType::MouseAxis(axis), ElementState::Released);
+                player_set
+                    .change_key_state(InputAxisType::MouseAxisDiff(axis), ElementState::Released);
+            }
+            for direction in [
+                MouseWheelDirection::Up,
+                MouseWheelDirection::Down,
+                MouseWheelDirection::Left,
+                MouseWheelDirection::Right,
+            ] {
+                player_set
+                    .change_key_state(InputAxisType::MouseWheel(direction), ElementState::Released);
+                player_set.change_axis_state(
+                    InputAxisType::MouseWheel(direction),
                     ElementState::Released,
+                    None,
                 );
-            };
+            }
         }
 
-        self.mouse_moved_this_tick = false;
+        self.cursor_moved_windows.clear();
+
+        self.received_text.clear();
 
-        self.mouse_wheel_moved_this_tick = false;
+        self.mouse_wheel_delta = Vec2::ZERO;
 
         self.mouse_delta = None;
     }
@@ -822,6 +2373,7 @@ where
             return Some(InputHandle {
                 input_set,
                 input_type,
+                input_source: self.input_id_to_input_source.get(&component.id).copied(),
             });
         }
         None
@@ -833,31 +2385,331 @@ where
         component
     }
 
-    #[allow(dead_code)]
+    pub fn consume_buffered(&mut self, component: &'_ InputId, name: BindingType) -> bool {
+        self.input_id_to_inputset
+            .get_mut(&component.id)
+            .map_or(false, |input_set| input_set.consume_buffered(name))
+    }
+
     pub fn stop_input_tracking(&mut self, component: &'_ InputId) {
         self.input_id_to_inputset.remove(&component.id);
         self.input_id_to_input_type.remove(&component.id);
+        self.input_id_to_window.remove(&component.id);
+        self.input_id_to_input_source.remove(&component.id);
+        self.text_input_ids.remove(&component.id);
+        self.device_claims.release_player(component.id);
+        self.rollback.remove_player(component.id);
+    }
+
+    pub fn set_remote(&mut self, component: &'_ InputId, remote: bool) {
+        let previous = self.device_claims.clone();
+        self.device_claims.set_remote(component.id, remote);
+        for device in self.get_devices() {
+            self.release_unaccepted(&previous, device);
+        }
+    }
+
+    pub fn claim_device(&mut self, component: &'_ InputId, device: InputDevice) -> bool {
+        let previous = self.device_claims.clone();
+        let claimed = self.device_claims.claim(component.id, device, None);
+        self.release_unaccepted(&previous, device);
+        claimed
+    }
+
+    pub fn claim_device_inputs(
+        &mut self,
+        component: &'_ InputId,
+        device: InputDevice,
+        inputs: &[InputAxisType],
+    ) -> bool {
+        let inputs = inputs.iter().cloned().collect();
+        let previous = self.device_claims.clone();
+        let claimed = self.device_claims.claim(component.id, device, Some(inputs));
+        self.release_unaccepted(&previous, device);
+        claimed
+    }
+
+    pub fn release_device(&mut self, component: &'_ InputId, device: InputDevice) {
+        let previous = self.device_claims.clone();
+        self.device_claims.release(component.id, device);
+        self.release_unaccepted(&previous, device);
+    }
+
+    fn release_unaccepted(&mut self, previous: &DeviceClaims, device: InputDevice) {
+        let claims = &self.device_claims;
+        for (player_id, player_set) in self.input_id_to_inputset.iter_mut() {
+            player_set.release_inputs(|input| {
+                device.produces(input)
+                    && previous.accepts(*player_id, device, Some(input))
+                    && !claims.accepts(*player_id, device, Some(input))
+            });
+        }
+    }
+
+    pub fn get_devices(&self) -> Vec<InputDevice> {
+        let mut gamepads: Vec<Gamepad> = self.connected_gamepads.iter().copied().collect();
+        gamepads.sort_by_key(|gamepad| gamepad.0);
+        let mut devices = vec![InputDevice::Keyboard, InputDevice::Mouse];
+        devices.extend(gamepads.into_iter().map(InputDevice::Gamepad));
+        devices
+    }
+
+    pub fn get_free_devices(&self) -> Vec<InputDevice> {
+        self.get_devices()
+            .into_iter()
+            .filter(|device| self.device_claims.get_owners(*device).is_empty())
+            .collect()
+    }
+
+    pub fn get_device_owners(&self, device: InputDevice) -> Vec<InputId> {
+        self.device_claims
+            .get_owners(device)
+            .into_iter()
+            .map(InputId::new)
+            .collect()
+    }
+
+    pub fn get_player_devices(&self, component: &'_ InputId) -> Vec<InputDevice> {
+        self.device_claims.get_player_devices(component.id)
+    }
+
+    pub fn set_input_window(&mut self, component: &'_ InputId, window: Option<WindowId>) {
+        match window {
+            Some(window) => {
+                self.input_id_to_window.insert(component.id, window);
+            }
+            None => {
+                self.input_id_to_window.remove(&component.id);
+            }
+        }
     }
 
-    #[allow(dead_code)]
     pub fn get_input_source(&self) -> Option<InputSource> {
         self.last_input_source
     }
 
-    #[allow(dead_code)]
+    pub fn set_text_input(&mut self, enabled: bool) {
+        self.text_input_for_all = enabled;
+    }
+
+    pub fn set_player_text_input(&mut self, component: &'_ InputId, enabled: bool) {
+        if enabled {
+            self.text_input_ids.insert(component.id);
+        } else {
+            self.text_input_ids.remove(&component.id);
+        }
+    }
+
+    pub fn is_text_input(&self, component: &'_ InputId) -> bool {
+        self.text_input_for_all || self.text_input_ids.contains(&component.id)
+    }
+
+    pub fn set_text_input_allowed_keys(&mut self, keys: Vec<InputAxisType>) {
+        self.text_input_allowed_keys = keys;
+    }
+
+    pub fn get_text_input(&self, component: &'_ InputId) -> Option<&str> {
+        if self.is_text_input(component) {
+            Some(&self.received_text)
+        } else {
+            None
+        }
+    }
+
+    pub fn set_release_on_focus_loss(&mut self, enabled: bool) {
+        self.release_on_focus_loss = enabled;
+    }
+
+    pub fn set_rumble_backend(&mut self, backend: Box<dyn RumbleBackend>) {
+        self.rumble_backend = Some(backend);
+    }
+
+    pub fn get_player_gamepad(&self, component: &'_ InputId) -> Option<Gamepad> {
+        let claimed = self
+            .device_claims
+            .get_player_devices(component.id)
+            .into_iter()
+            .find_map(|device| match device {
+                InputDevice::Gamepad(gamepad) => Some(gamepad),
+                _ => None,
+            });
+        let gamepad = claimed.or_else(|| {
+            let gamepad = Gamepad(component.id as usize);
+            self.device_claims
+                .get_owners(InputDevice::Gamepad(gamepad))
+                .is_empty()
+                .then(|| gamepad)
+        })?;
+        self.connected_gamepads.contains(&gamepad).then(|| gamepad)
+    }
+
+    pub fn rumble(&mut self, component: &'_ InputId, request: RumbleRequest) {
+        if let Some(gamepad) = self.get_player_gamepad(component) {
+            self.rumble.add(gamepad, request);
+        }
+    }
+
+    pub fn stop_rumble(&mut self, component: &'_ InputId) {
+        if let Some(gamepad) = self.get_player_gamepad(component) {
+            self.rumble.stop(gamepad);
+        }
+    }
+
+    pub(crate) fn update_rumble(&mut self, delta_seconds: f32) {
+        let changes = self.rumble.update(delta_seconds);
+        if let Some(backend) = self.rumble_backend.as_mut() {
+            for (gamepad, strong, weak) in changes {
+                backend.set_motors(gamepad, strong, weak);
+            }
+        }
+    }
+
+    pub fn get_player_input_source(&self, component: &'_ InputId) -> Option<InputSource> {
+        self.input_id_to_input_source.get(&component.id).copied()
+    }
+
     pub fn get_mouse_postion(&self) -> Option<Vec2> {
         self.mouse_position
     }
 
-    #[allow(dead_code)]
     pub fn get_mouse_delta(&self) -> Option<Vec2> {
         self.mouse_delta
     }
 
+    pub fn get_mouse_window(&self) -> Option<WindowId> {
+        self.mouse_window
+    }
+
+    pub fn cursor_ray(
+        &self,
+        projection: Mat4,
+        camera_transform: &GlobalTransform,
+    ) -> Option<CursorRay> {
+        let window_size = self.window_sizes.get(&self.mouse_window?)?;
+        let ndc_position = (self.mouse_position? / *window_size) * 2.0 - Vec2::ONE;
+        let ndc_to_world = camera_transform.compute_matrix() * projection.inverse();
+        let near = ndc_to_world.project_point3(ndc_position.extend(0.0));
+        let far = ndc_to_world.project_point3(ndc_position.extend(1.0));
+        Some(CursorRay {
+            origin: near,
+            direction: (far - near).normalize(),
+        })
+    }
+
     pub fn apply_config(&mut self, config: &InputConfig<BindingType>) {
         for (_, set) in self.available_sets.iter_mut() {
             set.apply_config(config);
         }
+        for (_, player_set) in self.input_id_to_inputset.iter_mut() {
+            player_set.release_all();
+            player_set.apply_config(config);
+        }
+        self.config = config.clone();
+    }
+}
+
+impl<InputType, BindingType> UserInputHandle<InputType, BindingType>
+where
+    InputType: PartialEq + Eq + Hash + Copy + Clone + Send + Sync,
+    BindingType: PartialEq + Eq + Hash + Copy + Clone + Send + Sync + Ord,
+{
+    pub fn snapshot(&self, component: &'_ InputId, frame: u32) -> Option<InputSnapshot> {
+        self.input_id_to_inputset
+            .get(&component.id)
+            .map(|input_set| input_set.snapshot(frame))
+    }
+
+    pub fn apply_snapshot(&mut self, component: &'_ InputId, snapshot: InputSnapshot) {
+        self.injected_inputs
+            .push(InjectedInput::Snapshot(component.id, snapshot));
+    }
+
+    pub fn set_rollback_frames(&mut self, frames: usize) {
+        self.rollback.set_capacity(frames);
+    }
+
+    pub fn save_frame(&mut self, frame: u32) {
+        self.rollback.save(SavedFrame {
+            frame,
+            snapshots: self
+                .input_id_to_inputset
+                .iter()
+                .map(|(id, input_set)| (*id, input_set.snapshot(frame)))
+                .collect(),
+            raw_states: self
+                .input_id_to_inputset
+                .iter()
+                .map(|(id, input_set)| {
+                    let mut raw = input_set.raw_state();
+                    raw.source = self.input_id_to_input_source.get(id).copied();
+                    (*id, raw)
+                })
+                .collect(),
+            last_source: self.last_input_source,
+        });
+    }
+
+    pub fn restore_frame(&mut self, frame: u32) -> bool {
+        let saved = match self.rollback.get(frame) {
+            Some(saved) => saved.clone(),
+            None => return false,
+        };
+        let previous_frame = frame.wrapping_sub(1);
+        for (id, snapshot) in saved.snapshots {
+            if !self.input_id_to_inputset.contains_key(&id) {
+                continue;
+            }
+            let (previous, snapshot) = if self.device_claims.is_remote(id) {
+                let (previous, _) = self.rollback.get_remote_input(id, previous_frame);
+                let (snapshot, predicted) = self.rollback.get_remote_input(id, frame);
+                if predicted {
+                    self.rollback.add_prediction(id, snapshot.clone());
+                }
+                (previous, snapshot)
+            } else {
+                // Without the previous frame edges of `frame` are lost, states are still right
+                let previous = self
+                    .rollback
+                    .get(previous_frame)
+                    .and_then(|previous| previous.snapshots.get(&id))
+                    .cloned()
+                    .unwrap_or_else(|| snapshot.clone());
+                (previous, snapshot)
+            };
+            let player_set = self.input_id_to_inputset.get_mut(&id).unwrap();
+            player_set.apply_snapshot(&previous);
+            player_set.update_states();
+            player_set.apply_snapshot(&snapshot);
+            if self.device_claims.is_remote(id) {
+                continue;
+            }
+            if let Some(raw) = saved.raw_states.get(&id) {
+                player_set.restore_raw_state(raw);
+                match raw.source {
+                    Some(source) => self.input_id_to_input_source.insert(id, source),
+                    None => self.input_id_to_input_source.remove(&id),
+                };
+            }
+        }
+        self.last_input_source = saved.last_source;
+        true
+    }
+
+    pub fn add_remote_snapshot(
+        &mut self,
+        component: &'_ InputId,
+        snapshot: InputSnapshot,
+    ) -> Option<u32> {
+        self.rollback.add_confirmed(component.id, snapshot)
+    }
+
+    pub fn apply_remote_frame(&mut self, component: &'_ InputId, frame: u32) -> bool {
+        let (snapshot, predicted) = self.rollback.get_remote_input(component.id, frame);
+        if predicted {
+            self.rollback.add_prediction(component.id, snapshot.clone());
+        }
+        self.apply_snapshot(component, snapshot);
+        predicted
     }
 }
 
@@ -868,6 +2720,7 @@ where
 {
     input_set: &'a UserInputSet<BindingType>,
     input_type: &'a InputType,
+    input_source: Option<InputSource>,
 }
 
 impl<BindingType, InputType> InputHandle<'_, BindingType, InputType>
@@ -875,18 +2728,953 @@ where
     BindingType: PartialEq + Eq + Hash + Copy + Clone + Send + Sync,
     InputType: PartialEq + Eq + Hash + Copy + Clone + Send + Sync,
 {
-    #[allow(dead_code)]
     pub fn get_axis_value(&self, name: BindingType) -> Option<f32> {
         self.input_set.get_axis_value(name)
     }
 
-    #[allow(dead_code)]
     pub fn get_key_state(&self, name: BindingType) -> Option<ElementState> {
         self.input_set.get_key_state(name)
     }
 
-    #[allow(dead_code)]
     pub fn get_input_type(&self) -> &'_ InputType {
         self.input_type
     }
+
+    pub fn get_input_source(&self) -> Option<InputSource> {
+        self.input_source
+    }
+
+    pub fn get_input_set(&self) -> &'_ UserInputSet<BindingType> {
+        self.input_set
+    }
+
+    pub fn is_key_active(&self, name: BindingType) -> Option<bool> {
+        self.input_set.is_key_active(name)
+    }
+
+    pub fn is_buffered(&self, name: BindingType) -> bool {
+        self.input_set.is_buffered(name)
+    }
+
+    pub fn get_fixed_key_state(&self, name: BindingType) -> Option<ElementState> {
+        self.input_set.get_fixed_key_state(name)
+    }
+
+    pub fn get_fixed_axis_value(&self, name: BindingType) -> Option<f32> {
+        self.input_set.get_fixed_axis_value(name)
+    }
+
+    pub fn get_fixed_axis_delta(&self, name: BindingType) -> Option<f32> {
+        self.input_set.get_fixed_axis_delta(name)
+    }
+
+    pub fn get_bound_inputs(&self, name: BindingType, source: InputSource) -> Vec<InputAxisType> {
+        self.input_set
+            .get_bound_inputs(name)
+            .into_iter()
+            .filter(|input| input.input_source() == source)
+            .collect()
+    }
+
+    pub fn get_glyphs<'g, Glyph>(
+        &self,
+        name: BindingType,
+        source: InputSource,
+        table: &'g GlyphTable<Glyph>,
+        layout: GamepadLayout,
+    ) -> Vec<&'g Glyph> {
+        self.get_bound_inputs(name, source)
+            .iter()
+            .filter_map(|input| table.get(input, layout))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{
+        input::{mouse::MouseScrollUnit, ElementState},
+        math::Vec2,
+        prelude::{
+            Gamepad, GamepadAxisType, GamepadButtonType, GamepadEventType, KeyCode, MouseButton,
+        },
+        window::WindowId,
+    };
+
+    use super::{
+        ActionMetadata, AxisDirection, InputAxisType, InputSource, MouseAxisType,
+        MouseWheelDirection, UserInputSet,
+    };
+    use crate::{
+        config::InputConfig,
+        device::InputDevice,
+        input_id::InputId,
+        network::InputSnapshot,
+        rumble::{InMemoryRumbleBackend, RumbleRequest},
+        testing::tests::{add_player, test_handle, TestBinding, TestHandle, TestInput},
+    };
+
+    // Runs input processing of one frame without an app, as `input_system` does
+    fn process_frame(handle: &mut TestHandle) {
+        handle.begin_frame();
+        handle.mark_processed();
+        handle.process_injected_inputs();
+        handle.flush_mouse_motion();
+        handle.update_time(1.0 / 60.0);
+    }
+
+    fn key_state(
+        handle: &TestHandle,
+        input_id: &InputId,
+        name: TestBinding,
+    ) -> Option<ElementState> {
+        handle.to_handle(input_id).unwrap().get_key_state(name)
+    }
+
+    fn axis_value(handle: &TestHandle, input_id: &InputId, name: TestBinding) -> Option<f32> {
+        handle.to_handle(input_id).unwrap().get_axis_value(name)
+    }
+
+    #[test]
+    fn mouse_motion_and_cursor_position_are_independent() {
+        let mut set = UserInputSet::new();
+        set.begin_axis(TestBinding::MoveX)
+            .add(InputAxisType::MouseAxisDiff(MouseAxisType::X));
+        set.begin_axis(TestBinding::Look)
+            .add(InputAxisType::MouseAxis(MouseAxisType::X));
+        let (mut handle, input_id) = test_handle(set);
+
+        // Locked cursor reports motion without cursor position
+        handle.move_mouse(Vec2::new(3.0, 0.0));
+        process_frame(&mut handle);
+        assert_eq!(
+            axis_value(&handle, &input_id, TestBinding::MoveX),
+            Some(3.0)
+        );
+        assert_eq!(axis_value(&handle, &input_id, TestBinding::Look), None);
+
+        handle.move_cursor(Vec2::new(100.0, 50.0));
+        process_frame(&mut handle);
+        assert_eq!(axis_value(&handle, &input_id, TestBinding::MoveX), None);
+        assert_eq!(
+            axis_value(&handle, &input_id, TestBinding::Look),
+            Some(100.0)
+        );
+    }
+
+    #[test]
+    fn wheel_notch_is_pressed_once_per_line() {
+        let mut set = UserInputSet::new();
+        set.begin_key(TestBinding::Jump)
+            .add(&[InputAxisType::MouseWheel(MouseWheelDirection::Up)]);
+        set.begin_axis(TestBinding::MoveX)
+            .add(InputAxisType::MouseAxis(MouseAxisType::Wheel));
+        let (mut handle, input_id) = test_handle(set);
+
+        // Default 20 pixels per line, half a line gives no notch yet
+        handle.scroll_mouse_wheel(Vec2::new(0.0, 10.0), MouseScrollUnit::Pixel);
+        process_frame(&mut handle);
+        assert_eq!(key_state(&handle, &input_id, TestBinding::Jump), None);
+        assert_eq!(
+            axis_value(&handle, &input_id, TestBinding::MoveX),
+            Some(0.5)
+        );
+
+        handle.scroll_mouse_wheel(Vec2::new(0.0, 10.0), MouseScrollUnit::Pixel);
+        process_frame(&mut handle);
+        assert_eq!(
+            key_state(&handle, &input_id, TestBinding::Jump),
+            Some(ElementState::Pressed)
+        );
+
+        // Wheel axis lives for a single frame
+        process_frame(&mut handle);
+        assert_eq!(axis_value(&handle, &input_id, TestBinding::MoveX), None);
+    }
+
+    fn cursor_set() -> UserInputSet<TestBinding> {
+        let mut set = UserInputSet::new();
+        set.begin_axis(TestBinding::MoveX)
+            .add(InputAxisType::MouseAxisCentered(MouseAxisType::X));
+        set.begin_axis(TestBinding::Look)
+            .add(InputAxisType::MouseAxis(MouseAxisType::X));
+        set
+    }
+
+    #[test]
+    fn cursor_position_is_normalized_by_window_size() {
+        let (mut handle, input_id) = test_handle(cursor_set());
+
+        handle.set_window_size(WindowId::primary(), Vec2::new(200.0, 100.0));
+        handle.move_cursor(Vec2::new(150.0, 50.0));
+        process_frame(&mut handle);
+        assert_eq!(
+            axis_value(&handle, &input_id, TestBinding::MoveX),
+            Some(0.5)
+        );
+
+        // Cursor axises are released on the frame after the cursor stops
+        process_frame(&mut handle);
+        process_frame(&mut handle);
+        assert_eq!(axis_value(&handle, &input_id, TestBinding::MoveX), None);
+    }
+
+    #[test]
+    fn cursor_axises_are_released_per_window() {
+        let (mut handle, first) = test_handle(cursor_set());
+        let second = add_player(&mut handle, 1);
+        let other_window = WindowId::new();
+        handle.set_input_window(&first, Some(WindowId::primary()));
+        handle.set_input_window(&second, Some(other_window));
+
+        handle.move_cursor(Vec2::new(100.0, 10.0));
+        process_frame(&mut handle);
+        assert_eq!(axis_value(&handle, &first, TestBinding::Look), Some(100.0));
+        assert_eq!(axis_value(&handle, &second, TestBinding::Look), None);
+
+        handle.move_cursor_in_window(other_window, Vec2::new(30.0, 10.0));
+        process_frame(&mut handle);
+        process_frame(&mut handle);
+        assert_eq!(axis_value(&handle, &first, TestBinding::Look), None);
+        assert_eq!(axis_value(&handle, &second, TestBinding::Look), Some(30.0));
+    }
+
+    #[test]
+    fn closed_window_size_is_dropped() {
+        let (mut handle, input_id) = test_handle(cursor_set());
+
+        handle.set_window_size(WindowId::primary(), Vec2::new(200.0, 100.0));
+        handle.retain_window_sizes(|_| false);
+        handle.move_cursor(Vec2::new(150.0, 50.0));
+        process_frame(&mut handle);
+        assert_eq!(axis_value(&handle, &input_id, TestBinding::MoveX), None);
+        assert_eq!(
+            axis_value(&handle, &input_id, TestBinding::Look),
+            Some(150.0)
+        );
+    }
+
+    #[test]
+    fn shared_mouse_keeps_source_of_gamepad_player() {
+        let (mut handle, first) = test_handle(cursor_set());
+        let second = add_player(&mut handle, 1);
+        assert!(handle.claim_device(&second, InputDevice::Gamepad(Gamepad(1))));
+
+        handle.set_gamepad_button(Gamepad(1), GamepadButtonType::South, 1.0);
+        process_frame(&mut handle);
+        assert_eq!(handle.get_player_input_source(&first), None);
+        assert_eq!(
+            handle.get_player_input_source(&second),
+            Some(InputSource::Gamepad)
+        );
+
+        handle.move_mouse(Vec2::new(10.0, 0.0));
+        process_frame(&mut handle);
+        assert_eq!(
+            handle.get_player_input_source(&first),
+            Some(InputSource::Mouse)
+        );
+        assert_eq!(
+            handle.get_player_input_source(&second),
+            Some(InputSource::Gamepad)
+        );
+    }
+
+    #[test]
+    fn config_applied_after_create_reaches_input_id() {
+        let mut set = UserInputSet::new();
+        set.begin_key(TestBinding::Jump)
+            .add(&[InputAxisType::KeyboardButton(KeyCode::Space)]);
+        let (mut handle, input_id) = test_handle(set);
+
+        let mut config = InputConfig::new();
+        config.rebind_axis(
+            InputAxisType::KeyboardButton(KeyCode::Space),
+            InputAxisType::KeyboardButton(KeyCode::Return),
+        );
+        handle.apply_config(&config);
+        assert_eq!(
+            handle
+                .to_handle(&input_id)
+                .unwrap()
+                .get_bound_inputs(TestBinding::Jump, InputSource::Keyboard),
+            vec![InputAxisType::KeyboardButton(KeyCode::Return)]
+        );
+
+        handle.press_key(KeyCode::Return);
+        process_frame(&mut handle);
+        assert_eq!(
+            key_state(&handle, &input_id, TestBinding::Jump),
+            Some(ElementState::Pressed)
+        );
+    }
+
+    #[test]
+    fn bound_axises_keep_declaration_order() {
+        let keys = vec![
+            InputAxisType::KeyboardButton(KeyCode::D),
+            InputAxisType::KeyboardButton(KeyCode::A),
+            InputAxisType::KeyboardButton(KeyCode::Right),
+            InputAxisType::KeyboardButton(KeyCode::Left),
+        ];
+        let mut set = UserInputSet::new();
+        {
+            let mut builder = set.begin_axis(TestBinding::MoveX);
+            for key in keys.iter() {
+                builder.add(key.clone());
+            }
+        }
+        let (handle, input_id) = test_handle(set);
+        assert_eq!(
+            handle
+                .to_handle(&input_id)
+                .unwrap()
+                .get_bound_inputs(TestBinding::MoveX, InputSource::Keyboard),
+            keys
+        );
+    }
+
+    #[test]
+    fn partial_key_code_claim_keeps_scan_code_bindings() {
+        let mut set = UserInputSet::new();
+        set.begin_key(TestBinding::Jump)
+            .add(&[InputAxisType::KeyboardScanCode(17)]);
+        let (mut handle, first) = test_handle(set);
+        let second = add_player(&mut handle, 1);
+        assert!(handle.claim_device_inputs(
+            &first,
+            InputDevice::Keyboard,
+            &[InputAxisType::KeyboardButton(KeyCode::W)]
+        ));
+        assert!(handle.claim_device_inputs(
+            &second,
+            InputDevice::Keyboard,
+            &[InputAxisType::KeyboardButton(KeyCode::Up)]
+        ));
+
+        handle.finish_processing();
+        handle.process_keyboard_event(Some(KeyCode::W), Some(17), ElementState::Pressed);
+        assert_eq!(
+            key_state(&handle, &first, TestBinding::Jump),
+            Some(ElementState::Pressed)
+        );
+        assert_eq!(key_state(&handle, &second, TestBinding::Jump), None);
+
+        handle.finish_processing();
+        handle.process_keyboard_event(Some(KeyCode::W), Some(17), ElementState::Released);
+        assert_eq!(
+            key_state(&handle, &first, TestBinding::Jump),
+            Some(ElementState::Released)
+        );
+    }
+
+    #[test]
+    fn key_event_counts_once_per_binding() {
+        let mut set = UserInputSet::new();
+        set.begin_key(TestBinding::Jump).add(&[
+            InputAxisType::KeyboardButton(KeyCode::Space),
+            InputAxisType::KeyboardScanCode(57),
+        ]);
+        set.begin_key(TestBinding::Look)
+            .add(&[InputAxisType::KeyboardScanCode(57)]);
+        let (mut handle, input_id) = test_handle(set);
+
+        // Space and its scan code are one key, not a chord
+        handle.finish_processing();
+        handle.process_keyboard_event(Some(KeyCode::Space), Some(57), ElementState::Pressed);
+        assert_eq!(key_state(&handle, &input_id, TestBinding::Jump), None);
+        assert_eq!(
+            key_state(&handle, &input_id, TestBinding::Look),
+            Some(ElementState::Pressed)
+        );
+
+        handle.finish_processing();
+        handle.process_keyboard_event(Some(KeyCode::Space), Some(57), ElementState::Released);
+        assert_eq!(key_state(&handle, &input_id, TestBinding::Jump), None);
+        assert_eq!(
+            key_state(&handle, &input_id, TestBinding::Look),
+            Some(ElementState::Released)
+        );
+    }
+
+    #[test]
+    fn control_characters_are_not_typed() {
+        let (mut handle, input_id) = test_handle(UserInputSet::new());
+        handle.set_player_text_input(&input_id, true);
+
+        for character in ['h', '\u{8}', 'i', '\r', '\t', '\u{7f}'] {
+            handle.type_character(character);
+        }
+        process_frame(&mut handle);
+        assert_eq!(handle.get_text_input(&input_id), Some("hi"));
+    }
+
+    #[test]
+    fn rumble_without_gamepad_does_nothing() {
+        let (mut handle, input_id) = test_handle(UserInputSet::new());
+        let backend = InMemoryRumbleBackend::new();
+        handle.set_rumble_backend(Box::new(backend.clone()));
+
+        assert_eq!(handle.get_player_gamepad(&input_id), None);
+        handle.rumble(&input_id, RumbleRequest::new(1.0, 1.0, 1.0));
+        handle.update_rumble(0.1);
+        assert_eq!(backend.get_motors(Gamepad(0)), None);
+    }
+
+    #[test]
+    fn rumble_plays_on_claimed_gamepad() {
+        let (mut handle, input_id) = test_handle(UserInputSet::new());
+        let backend = InMemoryRumbleBackend::new();
+        handle.set_rumble_backend(Box::new(backend.clone()));
+        handle.process_gamepad(Gamepad(0), GamepadEventType::Connected);
+        handle.process_gamepad(Gamepad(2), GamepadEventType::Connected);
+        assert!(handle.claim_device(&input_id, InputDevice::Gamepad(Gamepad(2))));
+
+        assert_eq!(handle.get_player_gamepad(&input_id), Some(Gamepad(2)));
+        handle.rumble(&input_id, RumbleRequest::new(1.0, 0.5, 1.0));
+        handle.update_rumble(0.1);
+        assert_eq!(backend.get_motors(Gamepad(2)), Some((1.0, 0.5)));
+        assert_eq!(backend.get_motors(Gamepad(0)), None);
+
+        handle.stop_rumble(&input_id);
+        handle.update_rumble(0.1);
+        assert_eq!(backend.get_motors(Gamepad(2)), Some((0.0, 0.0)));
+    }
+
+    #[test]
+    fn rumble_expires_without_backend() {
+        let (mut handle, input_id) = test_handle(UserInputSet::new());
+        handle.process_gamepad(Gamepad(0), GamepadEventType::Connected);
+        handle.rumble(&input_id, RumbleRequest::new(1.0, 1.0, 0.1));
+        handle.update_rumble(0.2);
+
+        let backend = InMemoryRumbleBackend::new();
+        handle.set_rumble_backend(Box::new(backend.clone()));
+        handle.update_rumble(0.1);
+        assert_eq!(backend.get_motors(Gamepad(0)), None);
+    }
+
+    #[test]
+    fn injected_axis_button_uses_thresholds() {
+        let mut set = UserInputSet::new();
+        set.begin_key(TestBinding::Jump).add_axis(
+            InputAxisType::GamepadAxis(GamepadAxisType::RightZ),
+            AxisDirection::Positive,
+            0.5,
+            0.3,
+        );
+        let (mut handle, input_id) = test_handle(set);
+        let trigger = InputAxisType::GamepadAxis(GamepadAxisType::RightZ);
+
+        handle.inject_input(&input_id, trigger.clone(), ElementState::Pressed, Some(0.2));
+        process_frame(&mut handle);
+        assert_eq!(key_state(&handle, &input_id, TestBinding::Jump), None);
+
+        handle.inject_input(&input_id, trigger.clone(), ElementState::Pressed, Some(0.8));
+        process_frame(&mut handle);
+        assert_eq!(
+            key_state(&handle, &input_id, TestBinding::Jump),
+            Some(ElementState::Pressed)
+        );
+
+        handle.inject_input(&input_id, trigger, ElementState::Released, None);
+        process_frame(&mut handle);
+        assert_eq!(
+            key_state(&handle, &input_id, TestBinding::Jump),
+            Some(ElementState::Released)
+        );
+    }
+
+    #[test]
+    fn ramp_applies_only_to_digital_values() {
+        let mut set = UserInputSet::new();
+        set.begin_axis(TestBinding::MoveX)
+            .add(InputAxisType::KeyboardButton(KeyCode::D))
+            .add(InputAxisType::GamepadAxis(GamepadAxisType::LeftStickX))
+            .ramp(6.0, 3.0);
+        let (mut handle, input_id) = test_handle(set);
+
+        // Analog value is applied and dropped at once
+        handle.set_gamepad_axis(Gamepad(0), GamepadAxisType::LeftStickX, 0.5);
+        process_frame(&mut handle);
+        assert_eq!(
+            axis_value(&handle, &input_id, TestBinding::MoveX),
+            Some(0.5)
+        );
+        handle.set_gamepad_axis(Gamepad(0), GamepadAxisType::LeftStickX, 0.0);
+        process_frame(&mut handle);
+        assert_eq!(axis_value(&handle, &input_id, TestBinding::MoveX), None);
+
+        // Digital value ramps up and then decays after release
+        handle.press_key(KeyCode::D);
+        process_frame(&mut handle);
+        let pressed = axis_value(&handle, &input_id, TestBinding::MoveX).unwrap();
+        assert!(pressed > 0.0 && pressed < 1.0);
+        handle.release_key(KeyCode::D);
+        process_frame(&mut handle);
+        let released = axis_value(&handle, &input_id, TestBinding::MoveX).unwrap();
+        assert!(released > 0.0 && released < pressed);
+        process_frame(&mut handle);
+        assert_eq!(axis_value(&handle, &input_id, TestBinding::MoveX), None);
+    }
+
+    #[test]
+    fn held_key_repeats_after_delay() {
+        let mut set = UserInputSet::new();
+        set.begin_key(TestBinding::Jump)
+            .add(&[InputAxisType::KeyboardButton(KeyCode::Down)])
+            .repeat(0.5, 0.25);
+        let (mut handle, input_id) = test_handle(set);
+
+        handle.press_key(KeyCode::Down);
+        process_frame(&mut handle);
+        assert_eq!(
+            key_state(&handle, &input_id, TestBinding::Jump),
+            Some(ElementState::Pressed)
+        );
+
+        // 0.4 s held, still within the delay
+        let mut repeats = 0;
+        for _ in 0..24 {
+            process_frame(&mut handle);
+            if key_state(&handle, &input_id, TestBinding::Jump).is_some() {
+                repeats += 1;
+            }
+        }
+        assert_eq!(repeats, 0);
+
+        // 0.9 s held, repeated at 0.5 and 0.75 s
+        for _ in 0..30 {
+            process_frame(&mut handle);
+            if key_state(&handle, &input_id, TestBinding::Jump).is_some() {
+                repeats += 1;
+            }
+        }
+        assert_eq!(repeats, 2);
+
+        handle.release_key(KeyCode::Down);
+        process_frame(&mut handle);
+        assert_eq!(
+            key_state(&handle, &input_id, TestBinding::Jump),
+            Some(ElementState::Released)
+        );
+    }
+
+    #[test]
+    fn toggle_binding_latches_on_press() {
+        let mut set = UserInputSet::new();
+        set.begin_key(TestBinding::Jump)
+            .add(&[InputAxisType::KeyboardButton(KeyCode::C)]);
+        let (mut handle, input_id) = test_handle(set);
+        let mut config = InputConfig::new();
+        config.set_toggle(TestBinding::Jump, true);
+        handle.apply_config(&config);
+
+        let is_active = |handle: &TestHandle| {
+            handle
+                .to_handle(&input_id)
+                .unwrap()
+                .is_key_active(TestBinding::Jump)
+        };
+        for expected in [true, false] {
+            handle.press_key(KeyCode::C);
+            process_frame(&mut handle);
+            handle.release_key(KeyCode::C);
+            process_frame(&mut handle);
+            process_frame(&mut handle);
+            assert_eq!(is_active(&handle), Some(expected));
+        }
+    }
+
+    #[test]
+    fn switch_input_resets_toggles() {
+        let mut set = UserInputSet::new();
+        set.begin_key(TestBinding::Jump)
+            .add(&[InputAxisType::KeyboardButton(KeyCode::C)]);
+        let (mut handle, input_id) = test_handle(set);
+        handle.add_input(TestInput::Other, UserInputSet::new());
+        let mut config = InputConfig::new();
+        config.set_toggle(TestBinding::Jump, true);
+        handle.apply_config(&config);
+
+        let is_active = |handle: &TestHandle| {
+            handle
+                .to_handle(&input_id)
+                .unwrap()
+                .is_key_active(TestBinding::Jump)
+        };
+        let click = |handle: &mut TestHandle| {
+            handle.press_key(KeyCode::C);
+            process_frame(handle);
+            handle.release_key(KeyCode::C);
+            process_frame(handle);
+        };
+
+        click(&mut handle);
+        assert_eq!(is_active(&handle), Some(true));
+        // Set of the current type is kept, only the toggle is turned off
+        handle.switch_input(&input_id, TestInput::Other);
+        assert_eq!(is_active(&handle), Some(false));
+
+        click(&mut handle);
+        assert_eq!(is_active(&handle), Some(true));
+        // Current type gets a fresh copy of its set
+        handle.switch_input(&input_id, TestInput::Main);
+        assert_eq!(is_active(&handle), Some(false));
+    }
+
+    #[test]
+    fn buffered_press_is_consumed_once_within_window() {
+        let mut set = UserInputSet::new();
+        set.begin_key(TestBinding::Jump)
+            .add(&[InputAxisType::KeyboardButton(KeyCode::Space)])
+            .buffer(0.1);
+        let (mut handle, input_id) = test_handle(set);
+
+        handle.press_key(KeyCode::Space);
+        process_frame(&mut handle);
+        handle.release_key(KeyCode::Space);
+        for _ in 0..3 {
+            process_frame(&mut handle);
+        }
+        assert!(handle.consume_buffered(&input_id, TestBinding::Jump));
+        assert!(!handle.consume_buffered(&input_id, TestBinding::Jump));
+
+        // Not consumed within 0.1 s, the press is dropped
+        handle.press_key(KeyCode::Space);
+        process_frame(&mut handle);
+        handle.release_key(KeyCode::Space);
+        for _ in 0..10 {
+            process_frame(&mut handle);
+        }
+        assert!(!handle.consume_buffered(&input_id, TestBinding::Jump));
+    }
+
+    fn fixed_set() -> UserInputSet<TestBinding> {
+        let mut set = UserInputSet::new();
+        set.begin_axis(TestBinding::MoveX)
+            .add(InputAxisType::MouseAxisDiff(MouseAxisType::X));
+        set.begin_axis(TestBinding::Look)
+            .add(InputAxisType::GamepadAxis(GamepadAxisType::LeftStickX));
+        set
+    }
+
+    #[test]
+    fn nothing_is_accumulated_without_fixed_ticks() {
+        let (mut handle, input_id) = test_handle(fixed_set());
+        for _ in 0..10 {
+            handle.move_mouse(Vec2::new(3.0, 0.0));
+            process_frame(&mut handle);
+        }
+        // Frames before the first tick are not summed into it
+        handle.begin_fixed_tick();
+        let input_handle = handle.to_handle(&input_id).unwrap();
+        assert_eq!(
+            input_handle.get_fixed_axis_delta(TestBinding::MoveX),
+            Some(0.0)
+        );
+    }
+
+    #[test]
+    fn fixed_delta_sums_only_delta_axises() {
+        let (mut handle, input_id) = test_handle(fixed_set());
+        handle.begin_fixed_tick();
+        handle.set_gamepad_axis(Gamepad(0), GamepadAxisType::LeftStickX, 0.5);
+        for _ in 0..2 {
+            handle.move_mouse(Vec2::new(3.0, 0.0));
+            process_frame(&mut handle);
+        }
+        handle.begin_fixed_tick();
+        let input_handle = handle.to_handle(&input_id).unwrap();
+        assert_eq!(
+            input_handle.get_fixed_axis_delta(TestBinding::MoveX),
+            Some(6.0)
+        );
+        assert_eq!(
+            input_handle.get_fixed_axis_delta(TestBinding::Look),
+            Some(0.0)
+        );
+        assert_eq!(
+            input_handle.get_fixed_axis_value(TestBinding::Look),
+            Some(0.5)
+        );
+    }
+
+    #[test]
+    fn claim_releases_keys_held_by_other_input_ids() {
+        let mut set = UserInputSet::new();
+        set.begin_key(TestBinding::Jump)
+            .add(&[InputAxisType::KeyboardButton(KeyCode::Space)]);
+        let (mut handle, first) = test_handle(set);
+        let second = add_player(&mut handle, 1);
+
+        handle.press_key(KeyCode::Space);
+        process_frame(&mut handle);
+        process_frame(&mut handle);
+        assert!(handle.claim_device(&second, InputDevice::Keyboard));
+        assert_eq!(
+            key_state(&handle, &first, TestBinding::Jump),
+            Some(ElementState::Released)
+        );
+        assert_eq!(key_state(&handle, &second, TestBinding::Jump), None);
+        assert!(handle
+            .to_handle(&second)
+            .unwrap()
+            .is_key_active(TestBinding::Jump)
+            .unwrap());
+    }
+
+    #[test]
+    fn partial_claim_tracks_source_only_for_its_inputs() {
+        let (mut handle, first) = test_handle(UserInputSet::new());
+        let second = add_player(&mut handle, 1);
+        assert!(handle.claim_device_inputs(
+            &first,
+            InputDevice::Keyboard,
+            &[InputAxisType::KeyboardButton(KeyCode::W)]
+        ));
+        assert!(handle.claim_device_inputs(
+            &second,
+            InputDevice::Keyboard,
+            &[InputAxisType::KeyboardButton(KeyCode::Up)]
+        ));
+
+        handle.press_key(KeyCode::W);
+        process_frame(&mut handle);
+        assert_eq!(
+            handle.get_player_input_source(&first),
+            Some(InputSource::Keyboard)
+        );
+        assert_eq!(handle.get_player_input_source(&second), None);
+    }
+
+    #[test]
+    fn restore_predicts_remote_input_without_previous_frame() {
+        let mut set = UserInputSet::new();
+        set.begin_key(TestBinding::Jump)
+            .add(&[InputAxisType::KeyboardButton(KeyCode::Space)]);
+        let (mut handle, _) = test_handle(set);
+        let remote = add_player(&mut handle, 1);
+        handle.set_remote(&remote, true);
+        handle.set_rollback_frames(8);
+
+        // Frame 9 is not saved, remote input of frame 10 arrives late
+        process_frame(&mut handle);
+        handle.save_frame(10);
+        let snapshot = InputSnapshot {
+            frame: 10,
+            pressed: vec![1],
+            ..Default::default()
+        };
+        assert_eq!(handle.add_remote_snapshot(&remote, snapshot), None);
+
+        assert!(handle.restore_frame(10));
+        assert_eq!(
+            key_state(&handle, &remote, TestBinding::Jump),
+            Some(ElementState::Pressed)
+        );
+    }
+
+    #[test]
+    fn restore_reports_local_edges_of_the_frame() {
+        let mut set = UserInputSet::new();
+        set.begin_key(TestBinding::Jump)
+            .add(&[InputAxisType::KeyboardButton(KeyCode::Space)]);
+        let (mut handle, input_id) = test_handle(set);
+        handle.set_rollback_frames(8);
+
+        process_frame(&mut handle);
+        handle.save_frame(1);
+        handle.press_key(KeyCode::Space);
+        process_frame(&mut handle);
+        handle.save_frame(2);
+        process_frame(&mut handle);
+        handle.save_frame(3);
+
+        assert!(handle.restore_frame(2));
+        assert_eq!(
+            key_state(&handle, &input_id, TestBinding::Jump),
+            Some(ElementState::Pressed)
+        );
+        assert!(handle.restore_frame(3));
+        assert_eq!(key_state(&handle, &input_id, TestBinding::Jump), None);
+    }
+
+    #[test]
+    fn consumed_click_does_not_reach_bindings() {
+        let mut set = UserInputSet::new();
+        set.begin_key(TestBinding::Jump)
+            .add(&[InputAxisType::MouseButton(MouseButton::Left)]);
+        let (mut handle, input_id) = test_handle(set);
+
+        handle.consume_mouse_button(MouseButton::Left);
+        handle.press_mouse_button(MouseButton::Left);
+        process_frame(&mut handle);
+        assert_eq!(key_state(&handle, &input_id, TestBinding::Jump), None);
+        handle.release_mouse_button(MouseButton::Left);
+        process_frame(&mut handle);
+        assert_eq!(key_state(&handle, &input_id, TestBinding::Jump), None);
+
+        // Only one click is consumed
+        handle.press_mouse_button(MouseButton::Left);
+        process_frame(&mut handle);
+        assert_eq!(
+            key_state(&handle, &input_id, TestBinding::Jump),
+            Some(ElementState::Pressed)
+        );
+    }
+
+    #[test]
+    fn builders_set_metadata() {
+        let mut set = UserInputSet::new();
+        set.begin_key(TestBinding::Jump)
+            .add(&[InputAxisType::KeyboardButton(KeyCode::Space)])
+            .metadata(
+                ActionMetadata::new("Jump")
+                    .with_description("Jumps over gaps")
+                    .with_category("Movement"),
+            );
+        set.begin_axis(TestBinding::MoveX)
+            .add(InputAxisType::KeyboardButton(KeyCode::D))
+            .metadata(ActionMetadata::new("Move").with_rebindable(false));
+
+        let jump = set.get_metadata(TestBinding::Jump).unwrap();
+        assert_eq!(jump.display_name, "Jump");
+        assert_eq!(jump.description, "Jumps over gaps");
+        assert_eq!(jump.category, "Movement");
+        assert!(jump.rebindable);
+        let move_x = set.get_metadata(TestBinding::MoveX).unwrap();
+        assert_eq!(move_x.display_name, "Move");
+        assert!(!move_x.rebindable);
+        assert_eq!(set.get_categories(), vec!["Movement", ""]);
+    }
+
+    #[test]
+    fn binding_view_matches_set_state() {
+        let mut set = UserInputSet::new();
+        set.begin_key(TestBinding::Jump)
+            .add(&[InputAxisType::KeyboardButton(KeyCode::Space)]);
+        set.begin_axis(TestBinding::MoveX)
+            .add(InputAxisType::KeyboardButton(KeyCode::D))
+            .add(InputAxisType::KeyboardButton(KeyCode::A));
+        let (mut handle, input_id) = test_handle(set);
+
+        handle.press_key(KeyCode::Space);
+        process_frame(&mut handle);
+        let set = handle.get_player_set(&input_id).unwrap();
+        let jump = set
+            .get_binding_views()
+            .into_iter()
+            .find(|view| view.name == TestBinding::Jump)
+            .unwrap();
+        assert_eq!(jump.get_key_state(), set.get_key_state(TestBinding::Jump));
+        assert_eq!(jump.get_key_state(), Some(ElementState::Pressed));
+        assert!(jump.is_held());
+
+        let move_x = set
+            .get_binding_views()
+            .into_iter()
+            .find(|view| view.name == TestBinding::MoveX)
+            .unwrap();
+        assert_eq!(
+            move_x.get_default_keys(),
+            vec![
+                InputAxisType::KeyboardButton(KeyCode::D),
+                InputAxisType::KeyboardButton(KeyCode::A)
+            ]
+        );
+    }
+
+    fn jump_on_space() -> UserInputSet<TestBinding> {
+        let mut set = UserInputSet::new();
+        set.begin_key(TestBinding::Jump)
+            .add(&[InputAxisType::KeyboardButton(KeyCode::Space)]);
+        set
+    }
+
+    #[test]
+    fn release_all_inputs_releases_held_keys() {
+        let (mut handle, input_id) = test_handle(jump_on_space());
+
+        handle.press_key(KeyCode::Space);
+        process_frame(&mut handle);
+        process_frame(&mut handle);
+        handle.release_all_inputs();
+        process_frame(&mut handle);
+        assert_eq!(
+            key_state(&handle, &input_id, TestBinding::Jump),
+            Some(ElementState::Released)
+        );
+    }
+
+    #[test]
+    fn release_all_drops_unreported_press() {
+        let (mut handle, input_id) = test_handle(jump_on_space());
+
+        handle.press_key(KeyCode::Space);
+        handle.release_all_inputs();
+        process_frame(&mut handle);
+        assert_eq!(key_state(&handle, &input_id, TestBinding::Jump), None);
+
+        process_frame(&mut handle);
+        assert_eq!(key_state(&handle, &input_id, TestBinding::Jump), None);
+    }
+
+    #[test]
+    fn partial_claim_leaves_other_keys_to_unclaimed_players() {
+        let mut set = UserInputSet::new();
+        set.begin_key(TestBinding::Jump)
+            .add(&[InputAxisType::KeyboardButton(KeyCode::Up)]);
+        set.begin_key(TestBinding::Look)
+            .add(&[InputAxisType::KeyboardScanCode(17)]);
+        let (mut handle, first) = test_handle(set);
+        let second = add_player(&mut handle, 1);
+        assert!(handle.claim_device_inputs(
+            &first,
+            InputDevice::Keyboard,
+            &[InputAxisType::KeyboardButton(KeyCode::W)]
+        ));
+
+        handle.finish_processing();
+        handle.process_keyboard_event(Some(KeyCode::W), Some(17), ElementState::Pressed);
+        assert_eq!(
+            key_state(&handle, &first, TestBinding::Look),
+            Some(ElementState::Pressed)
+        );
+        assert_eq!(key_state(&handle, &second, TestBinding::Look), None);
+
+        handle.finish_processing();
+        handle.process_keyboard_event(Some(KeyCode::Up), Some(103), ElementState::Pressed);
+        assert_eq!(key_state(&handle, &first, TestBinding::Jump), None);
+        assert_eq!(
+            key_state(&handle, &second, TestBinding::Jump),
+            Some(ElementState::Pressed)
+        );
+    }
+
+    #[test]
+    fn release_after_restore_gives_one_edge() {
+        let (mut handle, input_id) = test_handle(jump_on_space());
+        handle.set_rollback_frames(8);
+
+        handle.press_key(KeyCode::Space);
+        process_frame(&mut handle);
+        handle.save_frame(1);
+        handle.release_key(KeyCode::Space);
+        process_frame(&mut handle);
+        handle.save_frame(2);
+
+        // Space is held again in the restored frame
+        assert!(handle.restore_frame(1));
+        process_frame(&mut handle);
+        assert_eq!(key_state(&handle, &input_id, TestBinding::Jump), None);
+        handle.release_key(KeyCode::Space);
+        process_frame(&mut handle);
+        assert_eq!(
+            key_state(&handle, &input_id, TestBinding::Jump),
+            Some(ElementState::Released)
+        );
+        process_frame(&mut handle);
+        assert_eq!(key_state(&handle, &input_id, TestBinding::Jump), None);
+    }
 }
@@ -0,0 +1,347 @@
+use std::hash::Hash;
+
+use bevy::{
+    input::{touch::Touches, Input},
+    math::Vec2,
+    prelude::{Entity, GlobalTransform, Local, MouseButton, Query, Res, ResMut},
+    ui::Node,
+    utils::HashMap,
+    window::Windows,
+};
+
+use crate::{input_id::InputId, user_input::UserInputHandle};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VirtualControlKind {
+    Button(u8),
+    Stick { id: u8, dead_zone: f32 },
+}
+
+#[derive(Debug, Clone)]
+pub struct VirtualControl {
+    pub input_id: Option<InputId>,
+    pub kind: VirtualControlKind,
+}
+
+impl VirtualControl {
+    pub fn button(input_id: Option<InputId>, button: u8) -> Self {
+        Self {
+            input_id,
+            kind: VirtualControlKind::Button(button),
+        }
+    }
+
+    pub fn stick(input_id: Option<InputId>, stick: u8, dead_zone: f32) -> Self {
+        Self {
+            input_id,
+            kind: VirtualControlKind::Stick {
+                id: stick,
+                dead_zone,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum VirtualPointer {
+    Touch(u64),
+    Cursor,
+}
+
+fn contains(center: Vec2, half_size: Vec2, position: Vec2) -> bool {
+    let offset = (position - center).abs();
+    offset.x <= half_size.x && offset.y <= half_size.y
+}
+
+pub(crate) fn virtual_control_system<InputType: 'static, KeyType: 'static>(
+    controls: Query<(Entity, &VirtualControl, &Node, &GlobalTransform)>,
+    windows: Option<Res<Windows>>,
+    touches: Option<Res<Touches>>,
+    mouse_buttons: Option<Res<Input<MouseButton>>>,
+    mut pointers: Local<HashMap<Entity, VirtualPointer>>,
+    mut user_input: ResMut<UserInputHandle<InputType, KeyType>>,
+) where
+    InputType: PartialEq + Eq + Hash + Copy + Clone + Send + Sync,
+    KeyType: PartialEq + Eq + Hash + Copy + Clone + Send + Sync,
+{
+    let cursor_position = windows
+        .as_ref()
+        .and_then(|windows| windows.get_primary())
+        .and_then(|window| window.cursor_position());
+
+    for (entity, control, node, transform) in controls.iter() {
+        let center = transform.translation.truncate();
+        let half_size = node.size / 2.0;
+
+        if !pointers.contains_key(&entity) {
+            let touch = touches.as_ref().and_then(|touches| {
+                touches
+                    .iter_just_pressed()
+                    .find(|touch| contains(center, half_size, touch.position()))
+                    .map(|touch| VirtualPointer::Touch(touch.id()))
+            });
+            let cursor = mouse_buttons
+                .as_ref()
+                .filter(|mouse_buttons| mouse_buttons.just_pressed(MouseButton::Left))
+                .and(cursor_position)
+                .filter(|position| contains(center, half_size, *position))
+                .map(|_| VirtualPointer::Cursor);
+            match touch.or(cursor) {
+                Some(pointer) => {
+                    if pointer == VirtualPointer::Cursor {
+                        user_input.consume_mouse_button(MouseButton::Left);
+                    }
+                    pointers.insert(entity, pointer);
+                }
+                None => continue,
+            }
+        }
+
+        let position = match pointers[&entity] {
+            VirtualPointer::Touch(id) => touches
+                .as_ref()
+                .and_then(|touches| touches.get_pressed(id))
+                .map(|touch| touch.position()),
+            VirtualPointer::Cursor => mouse_buttons
+                .as_ref()
+                .filter(|mouse_buttons| mouse_buttons.pressed(MouseButton::Left))
+                .and(cursor_position),
+        };
+        if position.is_none() {
+            pointers.remove(&entity);
+        }
+
+        let input_id = control.input_id.as_ref();
+        match control.kind {
+            VirtualControlKind::Button(button) => {
+                user_input.set_virtual_button(input_id, button, position.is_some());
+            }
+            VirtualControlKind::Stick { id, dead_zone } => {
+                let value = position.map_or(Vec2::ZERO, |position| {
+                    let value = (position - center) / half_size.max(Vec2::ONE);
+                    let length = value.length();
+                    if length <= dead_zone {
+                        Vec2::ZERO
+                    } else if length > 1.0 {
+                        value / length
+                    } else {
+                        value
+                    }
+                });
+                user_input.set_virtual_stick(input_id, id, value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{
+        app::Events,
+        input::{
+            touch::{touch_screen_input_system, TouchInput, TouchPhase, Touches},
+            ElementState, Input,
+        },
+        math::{Vec2, Vec3},
+        prelude::{AppBuilder, CoreStage, GlobalTransform, IntoSystem, MouseButton},
+        ui::Node,
+        window::{Window, WindowDescriptor, WindowId, Windows},
+    };
+
+    use super::VirtualControl;
+    use crate::{
+        input_id::InputId,
+        testing::{
+            advance_frame,
+            tests::{handle_mut, test_app, TestBinding},
+        },
+        user_input::{InputAxisType, UserInputSet, VirtualStickAxis},
+    };
+
+    fn virtual_app() -> (AppBuilder, InputId) {
+        let mut set = UserInputSet::new();
+        set.begin_key(TestBinding::Jump)
+            .add(&[InputAxisType::VirtualButton(0)]);
+        set.begin_key(TestBinding::Look)
+            .add(&[InputAxisType::MouseButton(MouseButton::Left)]);
+        set.begin_axis(TestBinding::MoveX)
+            .add(InputAxisType::VirtualStick(0, VirtualStickAxis::X));
+        let (mut app, input_id) = test_app(set);
+
+        let mut windows = Windows::default();
+        windows.add(Window::new(
+            WindowId::primary(),
+            &WindowDescriptor::default(),
+            800,
+            600,
+            1.0,
+            None,
+        ));
+        app.insert_resource(windows)
+            .insert_resource(Input::<MouseButton>::default())
+            .init_resource::<Touches>()
+            .add_event::<TouchInput>()
+            .add_system_to_stage(CoreStage::PreUpdate, touch_screen_input_system.system());
+        (app, input_id)
+    }
+
+    // UI nodes and pointers both have the origin in the bottom left corner
+    fn spawn_control(app: &mut AppBuilder, control: VirtualControl, center: Vec2) {
+        app.world_mut().spawn().insert_bundle((
+            control,
+            Node {
+                size: Vec2::new(100.0, 100.0),
+            },
+            GlobalTransform::from_translation(Vec3::new(center.x, center.y, 0.0)),
+        ));
+    }
+
+    fn touch(app: &mut AppBuilder, phase: TouchPhase, position: Vec2) {
+        app.world_mut()
+            .get_resource_mut::<Events<TouchInput>>()
+            .unwrap()
+            .send(TouchInput {
+                phase,
+                position,
+                force: None,
+                id: 0,
+            });
+    }
+
+    fn move_cursor(app: &mut AppBuilder, position: Vec2) {
+        app.world_mut()
+            .get_resource_mut::<Windows>()
+            .unwrap()
+            .get_primary_mut()
+            .unwrap()
+            .update_cursor_position_from_backend(Some(position));
+    }
+
+    // Window backend updates `Input<MouseButton>` and sends the event the plugin reads
+    fn set_left_button(app: &mut AppBuilder, pressed: bool) {
+        {
+            let mut mouse_buttons = app
+                .world_mut()
+                .get_resource_mut::<Input<MouseButton>>()
+                .unwrap();
+            mouse_buttons.update();
+            if pressed {
+                mouse_buttons.press(MouseButton::Left);
+            } else {
+                mouse_buttons.release(MouseButton::Left);
+            }
+        }
+        let mut handle = handle_mut(app);
+        if pressed {
+            handle.press_mouse_button(MouseButton::Left);
+        } else {
+            handle.release_mouse_button(MouseButton::Left);
+        }
+    }
+
+    fn key_state(
+        app: &mut AppBuilder,
+        input_id: &InputId,
+        name: TestBinding,
+    ) -> Option<ElementState> {
+        handle_mut(app)
+            .to_handle(input_id)
+            .unwrap()
+            .get_key_state(name)
+    }
+
+    fn stick_x(app: &mut AppBuilder, input_id: &InputId) -> Option<f32> {
+        handle_mut(app)
+            .to_handle(input_id)
+            .unwrap()
+            .get_axis_value(TestBinding::MoveX)
+    }
+
+    #[test]
+    fn click_on_button_presses_it_and_does_not_reach_mouse_bindings() {
+        let (mut app, input_id) = virtual_app();
+        spawn_control(
+            &mut app,
+            VirtualControl::button(None, 0),
+            Vec2::new(100.0, 100.0),
+        );
+
+        move_cursor(&mut app, Vec2::new(130.0, 70.0));
+        set_left_button(&mut app, true);
+        advance_frame(&mut app);
+        assert_eq!(
+            key_state(&mut app, &input_id, TestBinding::Jump),
+            Some(ElementState::Pressed)
+        );
+        assert_eq!(key_state(&mut app, &input_id, TestBinding::Look), None);
+
+        set_left_button(&mut app, false);
+        advance_frame(&mut app);
+        assert_eq!(
+            key_state(&mut app, &input_id, TestBinding::Jump),
+            Some(ElementState::Released)
+        );
+        assert_eq!(key_state(&mut app, &input_id, TestBinding::Look), None);
+
+        // Outside of the node the click is a regular mouse click
+        move_cursor(&mut app, Vec2::new(300.0, 100.0));
+        set_left_button(&mut app, true);
+        advance_frame(&mut app);
+        assert_eq!(key_state(&mut app, &input_id, TestBinding::Jump), None);
+        assert_eq!(
+            key_state(&mut app, &input_id, TestBinding::Look),
+            Some(ElementState::Pressed)
+        );
+    }
+
+    #[test]
+    fn touch_hit_test_uses_ui_origin() {
+        let (mut app, input_id) = virtual_app();
+        // Node in the bottom left corner of the 800x600 window
+        spawn_control(
+            &mut app,
+            VirtualControl::button(None, 0),
+            Vec2::new(50.0, 50.0),
+        );
+
+        // Same point measured from the top edge misses the node
+        touch(&mut app, TouchPhase::Started, Vec2::new(50.0, 550.0));
+        advance_frame(&mut app);
+        assert_eq!(key_state(&mut app, &input_id, TestBinding::Jump), None);
+        touch(&mut app, TouchPhase::Ended, Vec2::new(50.0, 550.0));
+        advance_frame(&mut app);
+
+        touch(&mut app, TouchPhase::Started, Vec2::new(50.0, 50.0));
+        advance_frame(&mut app);
+        assert_eq!(
+            key_state(&mut app, &input_id, TestBinding::Jump),
+            Some(ElementState::Pressed)
+        );
+    }
+
+    #[test]
+    fn stick_applies_dead_zone_and_radius() {
+        let (mut app, input_id) = virtual_app();
+        let center = Vec2::new(200.0, 200.0);
+        spawn_control(&mut app, VirtualControl::stick(None, 0, 0.1), center);
+
+        // Inside the dead zone
+        touch(&mut app, TouchPhase::Started, center + Vec2::new(4.0, 0.0));
+        advance_frame(&mut app);
+        assert_eq!(stick_x(&mut app, &input_id), None);
+
+        // Half way to the node edge
+        touch(&mut app, TouchPhase::Moved, center + Vec2::new(25.0, 0.0));
+        advance_frame(&mut app);
+        assert_eq!(stick_x(&mut app, &input_id), Some(0.5));
+
+        // Pointer keeps the stick after leaving the node, clamped to the edge
+        touch(&mut app, TouchPhase::Moved, center + Vec2::new(150.0, 0.0));
+        advance_frame(&mut app);
+        assert_eq!(stick_x(&mut app, &input_id), Some(1.0));
+
+        touch(&mut app, TouchPhase::Ended, center + Vec2::new(150.0, 0.0));
+        advance_frame(&mut app);
+        assert_eq!(stick_x(&mut app, &input_id), None);
+    }
+}